@@ -0,0 +1,59 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use picasound::util::video::{Pixel, VideoFrame};
+
+// Throughput is reported in frames, so the results read as frames per second.
+const RESOLUTIONS: [(&str, usize, usize); 2] = [("1080p", 1920, 1080), ("4k", 3840, 2160)];
+
+fn apply(c: &mut Criterion) {
+    let mut group = c.benchmark_group("apply");
+    group.throughput(Throughput::Elements(1));
+
+    for (name, width, height) in RESOLUTIONS {
+        let mut frame = VideoFrame::new(width, height);
+
+        group.bench_function(BenchmarkId::new("serial", name), |b| {
+            b.iter(|| frame.apply(|(x, y), pixel| pixel.set_grayscale(shade(x, y))))
+        });
+
+        group.bench_function(BenchmarkId::new("parallel", name), |b| {
+            b.iter(|| frame.par_apply(|(x, y), pixel| pixel.set_grayscale(shade(x, y))))
+        });
+    }
+
+    group.finish();
+}
+
+fn apply_zip(c: &mut Criterion) {
+    let mut group = c.benchmark_group("apply_zip");
+    group.throughput(Throughput::Elements(1));
+
+    for (name, width, height) in RESOLUTIONS {
+        let mut frame = VideoFrame::new(width, height);
+        let mut other = VideoFrame::new(width, height);
+
+        group.bench_function(BenchmarkId::new("serial", name), |b| {
+            b.iter(|| frame.apply_zip(&mut other, |_, pixel1, pixel2| blend(pixel1, pixel2)))
+        });
+
+        group.bench_function(BenchmarkId::new("parallel", name), |b| {
+            b.iter(|| frame.par_apply_zip(&mut other, |_, pixel1, pixel2| blend(pixel1, pixel2)))
+        });
+    }
+
+    group.finish();
+}
+
+fn shade(x: usize, y: usize) -> u8 {
+    ((x ^ y) & 0xff) as u8
+}
+
+// Same per-pixel work as the sum mode of the merge node.
+fn blend(pixel1: &mut Pixel, pixel2: &mut Pixel) {
+    pixel1.set_red_f(0.5 * pixel1.red_f() + 0.5 * pixel2.red_f());
+    pixel1.set_green_f(0.5 * pixel1.green_f() + 0.5 * pixel2.green_f());
+    pixel1.set_blue_f(0.5 * pixel1.blue_f() + 0.5 * pixel2.blue_f());
+}
+
+criterion_group!(benches, apply, apply_zip);
+criterion_main!(benches);
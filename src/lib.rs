@@ -7,6 +7,7 @@ pub mod load;
 pub mod options;
 pub mod pipeline;
 pub mod processors;
+pub mod runtime;
 pub mod sinks;
 pub mod sources;
 pub mod util;
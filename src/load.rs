@@ -1,11 +1,11 @@
 use std::{collections::HashMap, io};
 
-use petgraph::{algo::toposort, Graph};
+use petgraph::{algo::toposort, Direction, Graph};
 use serde::Deserialize;
 
 use crate::{
     options::from_yaml,
-    pipeline::{NodeFactory, NodeRef, NodeRegistry},
+    pipeline::{Node, NodeFactory, NodeRegistry, Pipeline},
     util::{video::VideoConfig, Error, InvalidPipeline},
 };
 
@@ -22,6 +22,7 @@ struct PipelineConfigVideo {
     width: Option<usize>,
     height: Option<usize>,
     fps: Option<usize>,
+    offline: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -83,13 +84,17 @@ impl PipelineConfig {
                     builder.fps(fps);
                 }
 
+                if let Some(offline) = video.offline {
+                    builder.offline(offline);
+                }
+
                 builder.build()
             }
             None => VideoConfig::default(),
         }
     }
 
-    pub fn pipeline(mut self, factory: &NodeFactory) -> Result<Vec<NodeRef>, Error> {
+    pub fn pipeline(mut self, factory: &NodeFactory) -> Result<Pipeline, Error> {
         let mut sinks = Vec::new();
 
         for (name, definition) in self.pipeline.iter() {
@@ -129,10 +134,22 @@ impl PipelineConfig {
 
         let config = self.video_config();
         let mut registry = NodeRegistry::new();
+        let mut depths = HashMap::new();
+        let mut levels = Vec::new();
 
         for node_id in sorted.into_iter() {
             let node_name = graph[node_id].clone();
             let definition = self.pipeline.remove(&node_name).unwrap();
+
+            // Length of the longest path from a source. All inputs of a node
+            // are sorted before it, so their depths are already known.
+            let depth = graph
+                .neighbors_directed(node_id, Direction::Incoming)
+                .map(|input_id| depths[&input_id] + 1)
+                .max()
+                .unwrap_or(0);
+            depths.insert(node_id, depth);
+
             let inputs = definition
                 .inputs
                 .as_slice()
@@ -147,6 +164,15 @@ impl PipelineConfig {
 
             let node = factory.construct(&definition.node_type, inputs, options, config)?;
 
+            // Sinks are not evaluated by the scheduler.
+            if !node.is_sink() {
+                if levels.len() <= depth {
+                    levels.resize_with(depth + 1, Vec::new);
+                }
+
+                levels[depth].push(node.clone());
+            }
+
             registry.register(node_name, node);
         }
 
@@ -155,6 +181,6 @@ impl PipelineConfig {
             .map(|sink_name| registry.get(sink_name).unwrap())
             .collect();
 
-        Ok(sinks)
+        Ok(Pipeline::new(levels, sinks, config))
     }
 }
@@ -1,20 +1,36 @@
-use std::{env, fs::File};
+use std::{env, fs::File, io};
 
-use picasound::{
-    load::PipelineConfig,
-    pipeline::{Node, NodeFactory},
-};
+use picasound::{load::PipelineConfig, pipeline::NodeFactory, runtime, sources::device};
 
 fn main() {
+    if env::args().skip(1).any(|arg| arg == "--list-devices") {
+        device::list_devices(&mut io::stdout().lock()).unwrap();
+        return;
+    }
+
     let path = env::args()
         .skip(1)
         .last()
         .expect("pipeline file not specified");
     let file = File::open(path).expect("could not read pipeline file");
     let config = PipelineConfig::from_reader(file).unwrap();
-    let sinks = config.pipeline(&NodeFactory::default()).unwrap();
+    let pipeline = config.pipeline(&NodeFactory::default()).unwrap();
+
+    let (interrupt_sender, interrupts) = crossbeam_channel::unbounded();
+    ctrlc::set_handler(move || {
+        _ = interrupt_sender.send(());
+    })
+    .expect("could not set the Ctrl-C handler");
+
+    let stats = runtime::run(pipeline, interrupts).unwrap();
 
-    for mut sink in sinks {
-        sink.start().unwrap();
+    for (i, stats) in stats.iter().enumerate() {
+        eprintln!(
+            "sink {}: {} frames delivered, {} dropped, {} duplicated",
+            i,
+            stats.delivered(),
+            stats.dropped(),
+            stats.duplicated()
+        );
     }
 }
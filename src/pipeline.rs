@@ -1,15 +1,23 @@
+pub mod scheduler;
+
 use std::{borrow::Borrow, collections::HashMap, fmt, hash::Hash, sync::Arc};
 
 use atomic_refcell::AtomicRefCell;
+use crossbeam_channel::Receiver;
 
+use self::scheduler::SinkPort;
 use crate::{
     options::Options,
-    processors::{average, circle, equalizer, loudness, merge, spectrum},
-    sinks::rtsp,
-    sources::{device, random_color},
+    processors::{
+        audio_filter, average, circle, envelope, equalizer, expr, loudness, map, merge, onset,
+        resample, spectrum, tempo,
+    },
+    sinks::{file as file_sink, rtsp},
+    sources::{device, file, gst_audio, pcm, random_color, test_signal},
     util::{
         audio::AudioBuffer,
         spectrum::Spectrum,
+        tempo::Tempo,
         video::{VideoConfig, VideoFrame},
         Error, FrameId,
     },
@@ -26,10 +34,34 @@ pub trait Node: Send + Sync + fmt::Debug {
         Ok(())
     }
 
+    // The handle must be obtained before the sink is started, because `start`
+    // blocks until the sink is stopped.
+    fn stop_handle(&self) -> StopHandle {
+        assert!(self.is_sink(), "only sinks can be stopped");
+        StopHandle::noop()
+    }
+
+    fn sink_port(&self) -> SinkPort {
+        panic!("sink_port not available")
+    }
+
+    // Messages about problems the node works around while running, e.g., a
+    // disconnected audio device. Like the stop handle, the receiver is obtained
+    // before the pipeline is started.
+    fn events(&self) -> Option<Receiver<String>> {
+        None
+    }
+
     fn has_capability(&self, cap: Capability) -> bool {
         false
     }
 
+    // Whether the node (or any of its inputs) ran out of data, e.g., a file
+    // source reached the end of the file.
+    fn is_finished(&self) -> bool {
+        false
+    }
+
     fn provide_audio_data(&mut self, id: FrameId) -> AudioBuffer {
         panic!("provide_audio_data not available")
     }
@@ -45,6 +77,10 @@ pub trait Node: Send + Sync + fmt::Debug {
     fn provide_number(&mut self, id: FrameId) -> f32 {
         panic!("provide_number not available")
     }
+
+    fn provide_tempo(&mut self, id: FrameId) -> Tempo {
+        panic!("provide_tempo not available")
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -54,44 +90,276 @@ pub enum Capability {
     ProvideVideoFrame,
     ProvideSpectrum,
     ProvideNumber,
+    ProvideTempo,
 }
 
+#[derive(Clone)]
+pub struct StopHandle(Arc<dyn Fn() + Send + Sync>);
+
+impl StopHandle {
+    pub fn new<F>(stop: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        Self(Arc::new(stop))
+    }
+
+    pub fn noop() -> Self {
+        Self::new(|| {})
+    }
+
+    pub fn stop(&self) {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for StopHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StopHandle").finish_non_exhaustive()
+    }
+}
+
+// Outputs of the node are cached for the last frame id, so that a node used as
+// input by multiple nodes is evaluated only once per frame.
 #[derive(Debug, Clone)]
-pub struct NodeRef(Arc<AtomicRefCell<dyn Node>>);
+pub struct NodeRef {
+    node: Arc<AtomicRefCell<dyn Node>>,
+    cache: Arc<AtomicRefCell<Cache>>,
+}
 
 impl NodeRef {
     pub fn new<N: Node + 'static>(node: N) -> Self {
-        Self(Arc::new(AtomicRefCell::new(node)))
+        Self {
+            node: Arc::new(AtomicRefCell::new(node)),
+            cache: Arc::new(AtomicRefCell::new(Cache::default())),
+        }
+    }
+
+    // Whether both refer to the same node. Compares the caches, which are
+    // created together with the node, to avoid comparing trait object
+    // pointers.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cache, &other.cache)
+    }
+
+    // Evaluates all outputs of the node for the given frame and stores them in
+    // the cache. Once the inputs of a node are prefetched, its evaluation only
+    // reads their caches and nodes not depending on each other can be evaluated
+    // in parallel.
+    pub fn prefetch(&self, id: FrameId, config: VideoConfig) {
+        let mut node = self.clone();
+
+        if node.has_capability(Capability::ProvideAudioData) {
+            node.provide_audio_data(id);
+        }
+
+        if node.has_capability(Capability::ProvideVideoFrame)
+            && AtomicRefCell::borrow(&self.cache)
+                .video_frame
+                .get(id)
+                .is_none()
+        {
+            // Render directly into the cached frame to avoid a copy.
+            let mut frame = AtomicRefCell::borrow_mut(&self.cache)
+                .video_frame
+                .value
+                .take()
+                .unwrap_or_else(|| VideoFrame::new(config.width(), config.height()));
+            frame.clear();
+
+            AtomicRefCell::borrow_mut(&self.node).provide_video_frame(id, &mut frame);
+            AtomicRefCell::borrow_mut(&self.cache)
+                .video_frame
+                .set(id, frame);
+        }
+
+        if node.has_capability(Capability::ProvideSpectrum) {
+            node.provide_spectrum(id);
+        }
+
+        if node.has_capability(Capability::ProvideNumber) {
+            node.provide_number(id);
+        }
+
+        if node.has_capability(Capability::ProvideTempo) {
+            node.provide_tempo(id);
+        }
     }
 }
 
 impl Node for NodeRef {
     fn is_sink(&self) -> bool {
-        AtomicRefCell::borrow(&self.0).is_sink()
+        AtomicRefCell::borrow(&self.node).is_sink()
     }
 
     fn start(&mut self) -> Result<(), Error> {
-        AtomicRefCell::borrow_mut(&self.0).start()
+        AtomicRefCell::borrow_mut(&self.node).start()
+    }
+
+    fn stop_handle(&self) -> StopHandle {
+        AtomicRefCell::borrow(&self.node).stop_handle()
+    }
+
+    fn sink_port(&self) -> SinkPort {
+        AtomicRefCell::borrow(&self.node).sink_port()
+    }
+
+    fn events(&self) -> Option<Receiver<String>> {
+        AtomicRefCell::borrow(&self.node).events()
     }
 
     fn has_capability(&self, cap: Capability) -> bool {
-        AtomicRefCell::borrow(&self.0).has_capability(cap)
+        AtomicRefCell::borrow(&self.node).has_capability(cap)
+    }
+
+    fn is_finished(&self) -> bool {
+        AtomicRefCell::borrow(&self.node).is_finished()
     }
 
     fn provide_audio_data(&mut self, id: FrameId) -> AudioBuffer {
-        AtomicRefCell::borrow_mut(&self.0).provide_audio_data(id)
+        if let Some(data) = AtomicRefCell::borrow(&self.cache).audio_data.get(id) {
+            return data.clone();
+        }
+
+        let data = AtomicRefCell::borrow_mut(&self.node).provide_audio_data(id);
+        AtomicRefCell::borrow_mut(&self.cache)
+            .audio_data
+            .set(id, data.clone());
+        data
     }
 
     fn provide_video_frame(&mut self, id: FrameId, frame: &mut VideoFrame) {
-        AtomicRefCell::borrow_mut(&self.0).provide_video_frame(id, frame)
+        if let Some(cached) = AtomicRefCell::borrow(&self.cache).video_frame.get(id) {
+            frame.copy_from(cached);
+            return;
+        }
+
+        AtomicRefCell::borrow_mut(&self.node).provide_video_frame(id, frame);
+
+        let mut cache = AtomicRefCell::borrow_mut(&self.cache);
+        match cache.video_frame.value.as_mut() {
+            // Reuse the allocated frame.
+            Some(cached) => {
+                cached.copy_from(frame);
+                cache.video_frame.id = id;
+            }
+            None => cache.video_frame.set(id, frame.clone()),
+        }
     }
 
     fn provide_spectrum(&mut self, id: FrameId) -> Spectrum {
-        AtomicRefCell::borrow_mut(&self.0).provide_spectrum(id)
+        if let Some(spectrum) = AtomicRefCell::borrow(&self.cache).spectrum.get(id) {
+            return spectrum.clone();
+        }
+
+        let spectrum = AtomicRefCell::borrow_mut(&self.node).provide_spectrum(id);
+        AtomicRefCell::borrow_mut(&self.cache)
+            .spectrum
+            .set(id, spectrum.clone());
+        spectrum
     }
 
     fn provide_number(&mut self, id: FrameId) -> f32 {
-        AtomicRefCell::borrow_mut(&self.0).provide_number(id)
+        if let Some(number) = AtomicRefCell::borrow(&self.cache).number.get(id) {
+            return *number;
+        }
+
+        let number = AtomicRefCell::borrow_mut(&self.node).provide_number(id);
+        AtomicRefCell::borrow_mut(&self.cache)
+            .number
+            .set(id, number);
+        number
+    }
+
+    fn provide_tempo(&mut self, id: FrameId) -> Tempo {
+        if let Some(tempo) = AtomicRefCell::borrow(&self.cache).tempo.get(id) {
+            return *tempo;
+        }
+
+        let tempo = AtomicRefCell::borrow_mut(&self.node).provide_tempo(id);
+        AtomicRefCell::borrow_mut(&self.cache).tempo.set(id, tempo);
+        tempo
+    }
+}
+
+// Constructed pipeline. Nodes other than sinks are grouped into levels, each
+// node depends only on nodes from the preceding levels.
+#[derive(Debug)]
+pub struct Pipeline {
+    levels: Vec<Vec<NodeRef>>,
+    sinks: Vec<NodeRef>,
+    config: VideoConfig,
+}
+
+impl Pipeline {
+    pub fn new(levels: Vec<Vec<NodeRef>>, sinks: Vec<NodeRef>, config: VideoConfig) -> Self {
+        Self {
+            levels,
+            sinks,
+            config,
+        }
+    }
+
+    pub fn levels(&self) -> &[Vec<NodeRef>] {
+        &self.levels
+    }
+
+    pub fn sinks(&self) -> &[NodeRef] {
+        &self.sinks
+    }
+
+    pub fn config(&self) -> VideoConfig {
+        self.config
+    }
+}
+
+#[derive(Default)]
+struct Cache {
+    audio_data: Cached<AudioBuffer>,
+    video_frame: Cached<VideoFrame>,
+    spectrum: Cached<Spectrum>,
+    number: Cached<f32>,
+    tempo: Cached<Tempo>,
+}
+
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cache")
+            .field("audio_data", &self.audio_data.id)
+            .field("video_frame", &self.video_frame.id)
+            .field("spectrum", &self.spectrum.id)
+            .field("number", &self.number.id)
+            .field("tempo", &self.tempo.id)
+            .finish()
+    }
+}
+
+struct Cached<T> {
+    id: FrameId,
+    value: Option<T>,
+}
+
+impl<T> Cached<T> {
+    fn get(&self, id: FrameId) -> Option<&T> {
+        if self.id == id {
+            self.value.as_ref()
+        } else {
+            None
+        }
+    }
+
+    fn set(&mut self, id: FrameId, value: T) {
+        self.id = id;
+        self.value = Some(value);
+    }
+}
+
+impl<T> Default for Cached<T> {
+    fn default() -> Self {
+        Self {
+            id: FrameId::default(),
+            value: None,
+        }
     }
 }
 
@@ -195,16 +463,28 @@ impl Default for NodeFactory {
         let mut factory = Self::empty();
 
         device::register(&mut factory);
+        file::register(&mut factory);
+        gst_audio::register(&mut factory);
+        pcm::register(&mut factory);
         random_color::register(&mut factory);
+        test_signal::register(&mut factory);
 
+        file_sink::register(&mut factory);
         rtsp::register(&mut factory);
 
+        audio_filter::register(&mut factory);
         average::register(&mut factory);
         circle::register(&mut factory);
+        envelope::register(&mut factory);
         equalizer::register(&mut factory);
+        expr::register(&mut factory);
         loudness::register(&mut factory);
+        map::register(&mut factory);
         merge::register(&mut factory);
+        onset::register(&mut factory);
+        resample::register(&mut factory);
         spectrum::register(&mut factory);
+        tempo::register(&mut factory);
 
         factory
     }
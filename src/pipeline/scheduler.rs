@@ -0,0 +1,467 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, SendTimeoutError, Sender, TrySendError};
+use rayon::prelude::*;
+
+use crate::util::{
+    audio::AudioBuffer,
+    inputs::{validate_inputs, Optional},
+    video::{VideoConfig, VideoFrame},
+    Error, FrameId,
+};
+
+use super::{Capability, Node, NodeRef, StopHandle};
+
+// Number of frames that can wait for a sink before the oldest one is dropped
+// (or, in offline mode, the scheduler blocks).
+const QUEUE_CAPACITY: usize = 2;
+
+// How often the scheduler checks for being stopped while blocked in offline
+// mode.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Inputs of a sink. The video input is mandatory, the audio input is muxed into
+// the output stream if present.
+#[derive(Debug, Clone)]
+pub struct SinkInputs {
+    pub video: NodeRef,
+    pub audio: Option<NodeRef>,
+}
+
+impl SinkInputs {
+    pub fn validate(inputs: Vec<NodeRef>) -> Result<Self, Error> {
+        let (video, audio) = validate_inputs(
+            inputs,
+            (
+                Capability::ProvideVideoFrame,
+                Optional(Capability::ProvideAudioData),
+            ),
+        )?;
+
+        Ok(Self { video, audio })
+    }
+
+    fn is_finished(&self) -> bool {
+        self.video.is_finished() || self.audio.as_ref().map_or(false, Node::is_finished)
+    }
+}
+
+// One rendered frame together with the audio belonging to it.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub index: u64,
+    pub video: Arc<VideoFrame>,
+    pub audio: Option<FrameAudio>,
+}
+
+// The sinks read the audio from the buffer up to the position of each frame.
+// This way, they get a continuous stream even if frames are dropped or
+// repeated.
+#[derive(Debug, Clone)]
+pub struct FrameAudio {
+    pub buffer: AudioBuffer,
+    // Sample clock position at the presentation time of the frame.
+    pub position: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct SinkStats {
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    duplicated: AtomicU64,
+}
+
+impl SinkStats {
+    // Frames passed to the sink.
+    pub fn delivered(&self) -> u64 {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    // Frames not consumed by the sink in time.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    // Frames repeated because the next frame was not ready in time.
+    pub fn duplicated(&self) -> u64 {
+        self.duplicated.load(Ordering::Relaxed)
+    }
+}
+
+// Connection between a sink and the scheduler. The scheduler evaluates the sink
+// inputs and delivers the frames to all receivers subscribed by the sink.
+#[derive(Debug, Clone)]
+pub struct SinkPort {
+    inputs: SinkInputs,
+    config: VideoConfig,
+    subscribers: Arc<Mutex<Subscribers>>,
+    stats: Arc<SinkStats>,
+    frames: Arc<Mutex<FramePool>>,
+}
+
+impl SinkPort {
+    pub fn new(inputs: SinkInputs, config: VideoConfig) -> Self {
+        Self {
+            inputs,
+            config,
+            subscribers: Arc::new(Mutex::new(Subscribers::default())),
+            stats: Arc::new(SinkStats::default()),
+            frames: Arc::new(Mutex::new(FramePool::default())),
+        }
+    }
+
+    pub fn has_audio(&self) -> bool {
+        self.inputs.audio.is_some()
+    }
+
+    pub fn stats(&self) -> Arc<SinkStats> {
+        self.stats.clone()
+    }
+
+    // The handle is stopped when the scheduler is finished and no more frames
+    // will be delivered, e.g., at the end of a file source.
+    pub fn on_close(&self, handle: StopHandle) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+
+        if subscribers.closed {
+            handle.stop();
+        } else {
+            subscribers.on_close.push(handle);
+        }
+    }
+
+    pub fn subscribe(&self) -> FrameReceiver {
+        let (sender, receiver) = crossbeam_channel::bounded(QUEUE_CAPACITY);
+        let alive = Arc::new(());
+
+        let mut subscribers = self.subscribers.lock().unwrap();
+
+        if !subscribers.closed {
+            subscribers.list.push(Subscriber {
+                sender,
+                receiver: receiver.clone(),
+                alive: Arc::downgrade(&alive),
+            });
+        }
+
+        FrameReceiver {
+            receiver,
+            last: None,
+            timeout: (!self.config.is_offline()).then(|| frame_period(self.config)),
+            stats: self.stats.clone(),
+            _alive: alive,
+        }
+    }
+
+    fn has_subscribers(&self) -> bool {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers
+            .list
+            .retain(|subscriber| subscriber.alive.strong_count() > 0);
+        !subscribers.list.is_empty()
+    }
+
+    fn render(&self, id: FrameId, index: u64) -> Frame {
+        let mut video = self.frames.lock().unwrap().take(self.config);
+        self.inputs
+            .video
+            .clone()
+            .provide_video_frame(id, Arc::get_mut(&mut video).unwrap());
+        self.frames.lock().unwrap().list.push(video.clone());
+
+        let audio = self.inputs.audio.clone().map(|mut input| {
+            let buffer = input.provide_audio_data(id);
+            // The latency delays only the visuals, not the audio output.
+            let position = buffer.output_position(id);
+            FrameAudio { buffer, position }
+        });
+
+        Frame {
+            index,
+            video,
+            audio,
+        }
+    }
+
+    // Returns false if the delivery was interrupted by stopping the scheduler.
+    fn deliver(&self, frame: Frame, stop: &Receiver<()>) -> bool {
+        let subscribers = self.subscribers.lock().unwrap();
+
+        for subscriber in subscribers.list.iter() {
+            let frame = frame.clone();
+
+            if self.config.is_offline() {
+                // Nothing is dropped in offline mode, the scheduler waits for
+                // the sink instead.
+                let mut frame = frame;
+                loop {
+                    match subscriber.sender.send_timeout(frame, POLL_INTERVAL) {
+                        Ok(()) => {
+                            self.stats.delivered.fetch_add(1, Ordering::Relaxed);
+                            break;
+                        }
+                        Err(SendTimeoutError::Timeout(unsent)) => {
+                            if stop.try_recv().is_ok() {
+                                return false;
+                            }
+
+                            frame = unsent;
+                        }
+                        Err(SendTimeoutError::Disconnected(_)) => break,
+                    }
+                }
+            } else {
+                match subscriber.sender.try_send(frame) {
+                    Ok(()) => {
+                        self.stats.delivered.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(TrySendError::Full(frame)) => {
+                        // Drop the oldest frame so that the sink gets the
+                        // freshest one.
+                        if subscriber.receiver.try_recv().is_ok() {
+                            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                        }
+
+                        if subscriber.sender.try_send(frame).is_ok() {
+                            self.stats.delivered.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    Err(TrySendError::Disconnected(_)) => {}
+                }
+            }
+        }
+
+        true
+    }
+
+    // Disconnects all receivers, which signals the end of stream to the sink.
+    fn close(&self) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.closed = true;
+        subscribers.list.clear();
+
+        for handle in subscribers.on_close.drain(..) {
+            handle.stop();
+        }
+    }
+}
+
+// Frames delivered before, reused once no subscriber holds them anymore.
+#[derive(Default)]
+struct FramePool {
+    list: Vec<Arc<VideoFrame>>,
+}
+
+impl FramePool {
+    fn take(&mut self, config: VideoConfig) -> Arc<VideoFrame> {
+        match self
+            .list
+            .iter_mut()
+            .position(|frame| Arc::get_mut(frame).is_some())
+        {
+            Some(index) => {
+                let mut frame = self.list.swap_remove(index);
+                Arc::get_mut(&mut frame).unwrap().clear();
+                frame
+            }
+            None => Arc::new(VideoFrame::new(config.width(), config.height())),
+        }
+    }
+}
+
+impl fmt::Debug for FramePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FramePool")
+            .field("len", &self.list.len())
+            .finish()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Subscribers {
+    list: Vec<Subscriber>,
+    closed: bool,
+    on_close: Vec<StopHandle>,
+}
+
+#[derive(Debug)]
+struct Subscriber {
+    sender: Sender<Frame>,
+    // Used for dropping the oldest frame when the queue is full.
+    receiver: Receiver<Frame>,
+    alive: Weak<()>,
+}
+
+#[derive(Debug)]
+pub struct FrameReceiver {
+    receiver: Receiver<Frame>,
+    last: Option<Frame>,
+    timeout: Option<Duration>,
+    stats: Arc<SinkStats>,
+    _alive: Arc<()>,
+}
+
+impl FrameReceiver {
+    // Blocks until the next frame is available. In real-time mode, the last
+    // frame is repeated if the next one does not come in time. Returns `None`
+    // when there are no more frames.
+    pub fn next(&mut self) -> Option<Frame> {
+        loop {
+            let result = match self.timeout {
+                Some(timeout) => self.receiver.recv_timeout(timeout),
+                None => self
+                    .receiver
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match result {
+                Ok(frame) => {
+                    self.last = Some(frame.clone());
+                    return Some(frame);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    // Before the first frame, there is nothing to repeat.
+                    if let Some(last) = self.last.as_ref() {
+                        self.stats.duplicated.fetch_add(1, Ordering::Relaxed);
+                        // It ends at the same audio position, so no audio is
+                        // repeated.
+                        return Some(last.clone());
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+}
+
+// Drives the pipeline. Ticks at the configured fps (or as fast as possible in
+// offline mode), evaluates the graph once per tick and fans the frames out to
+// all sinks.
+#[derive(Debug)]
+pub struct Scheduler {
+    levels: Vec<Vec<NodeRef>>,
+    ports: Vec<SinkPort>,
+    config: VideoConfig,
+    stop_sender: Sender<()>,
+    stop_receiver: Receiver<()>,
+}
+
+impl Scheduler {
+    pub fn new(levels: Vec<Vec<NodeRef>>, ports: Vec<SinkPort>, config: VideoConfig) -> Self {
+        let (stop_sender, stop_receiver) = crossbeam_channel::bounded(1);
+
+        Self {
+            levels,
+            ports,
+            config,
+            stop_sender,
+            stop_receiver,
+        }
+    }
+
+    pub fn stop_handle(&self) -> StopHandle {
+        let stop_sender = self.stop_sender.clone();
+        StopHandle::new(move || {
+            _ = stop_sender.try_send(());
+        })
+    }
+
+    // Runs until stopped or until any sink input is finished.
+    pub fn run(self) -> Result<(), Error> {
+        let start = Instant::now();
+        let mut tick = 0u64;
+
+        loop {
+            if self.config.is_offline() {
+                if self.stop_receiver.try_recv().is_ok() {
+                    break;
+                }
+            } else {
+                let deadline = start + tick_time(tick, self.config);
+                let now = Instant::now();
+
+                if now < deadline {
+                    match self.stop_receiver.recv_timeout(deadline - now) {
+                        Err(RecvTimeoutError::Timeout) => {}
+                        _ => break,
+                    }
+                } else {
+                    if self.stop_receiver.try_recv().is_ok() {
+                        break;
+                    }
+
+                    // Skip the ticks that were missed because of too slow
+                    // evaluation. The sinks repeat frames in the meantime.
+                    let period = frame_period(self.config);
+                    tick += ((now - deadline).as_nanos() / period.as_nanos()) as u64;
+                }
+            }
+
+            let id = if self.config.is_offline() {
+                FrameId::new()
+            } else {
+                FrameId::at(start + tick_time(tick, self.config))
+            };
+
+            let mut finished = false;
+            let mut stopped = false;
+
+            let active = self
+                .ports
+                .iter()
+                .map(SinkPort::has_subscribers)
+                .collect::<Vec<_>>();
+
+            if active.iter().any(|active| *active) {
+                self.evaluate(id);
+            }
+
+            for (port, active) in self.ports.iter().zip(active) {
+                if active {
+                    let frame = port.render(id, tick);
+                    stopped |= !port.deliver(frame, &self.stop_receiver);
+                }
+
+                finished |= port.inputs.is_finished();
+            }
+
+            tick += 1;
+
+            if finished || stopped {
+                break;
+            }
+        }
+
+        for port in self.ports.iter() {
+            port.close();
+        }
+
+        Ok(())
+    }
+
+    // Nodes within a level do not depend on each other, so they are evaluated
+    // in parallel. The sinks then get all inputs from the caches.
+    fn evaluate(&self, id: FrameId) {
+        for level in self.levels.iter() {
+            level
+                .par_iter()
+                .for_each(|node| node.prefetch(id, self.config));
+        }
+    }
+}
+
+fn frame_period(config: VideoConfig) -> Duration {
+    Duration::from_secs(1) / config.fps() as u32
+}
+
+fn tick_time(tick: u64, config: VideoConfig) -> Duration {
+    Duration::from_nanos(tick * 1_000_000_000 / config.fps() as u64)
+}
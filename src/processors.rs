@@ -1,6 +1,13 @@
+pub mod audio_filter;
 pub mod average;
 pub mod circle;
+pub mod envelope;
 pub mod equalizer;
+pub mod expr;
 pub mod loudness;
+pub mod map;
 pub mod merge;
+pub mod onset;
+pub mod resample;
 pub mod spectrum;
+pub mod tempo;
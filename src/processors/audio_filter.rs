@@ -0,0 +1,137 @@
+use crate::{
+    options::Options,
+    pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
+    util::{
+        audio::AudioBuffer,
+        filter::{Biquad, Coefficients, FilterType},
+        inputs::validate_inputs,
+        video::VideoConfig,
+        Error, FrameId,
+    },
+};
+
+#[derive(Debug)]
+pub struct AudioFilter {
+    input: NodeRef,
+    buf: AudioBuffer,
+    // One per channel, each keeps its own state.
+    filters: Vec<Biquad>,
+    position: u64,
+    last_id: FrameId,
+}
+
+impl AudioFilter {
+    pub fn new(inputs: Vec<NodeRef>, options: Options, config: VideoConfig) -> Result<Self, Error> {
+        let mut input = validate_inputs(inputs, Capability::ProvideAudioData)?;
+
+        let filter_type = options
+            .get("filter")
+            .map(FilterType::from_value)
+            .transpose()?
+            .unwrap_or(FilterType::Lowpass);
+
+        // Only the format of the buffer is needed here, the default id does not
+        // make the source advance.
+        let source = input.provide_audio_data(FrameId::default());
+        let nyquist = source.sample_rate() as f32 / 2.0;
+
+        let frequency = options
+            .get("frequency")
+            .unwrap_or(&1000.0.into())
+            .as_f32()
+            .filter(|frequency| *frequency > 0.0 && *frequency < nyquist)
+            .ok_or(Error::InvalidOptions)?;
+
+        let q = options
+            .get("q")
+            .unwrap_or(&0.707.into())
+            .as_f32()
+            .filter(|q| *q > 0.0)
+            .ok_or(Error::InvalidOptions)?;
+
+        // In dB.
+        let gain = options
+            .get("gain")
+            .unwrap_or(&0.0.into())
+            .as_f32()
+            .ok_or(Error::InvalidOptions)?;
+
+        let coefs = Coefficients::new(filter_type, source.sample_rate(), frequency, q, gain);
+        let filters = vec![Biquad::new(coefs); source.channels()];
+
+        let buf = AudioBuffer::with_channels(source.sample_rate(), config.fps(), source.channels())
+            .with_latency(source.latency());
+
+        Ok(Self {
+            input,
+            buf,
+            filters,
+            position: 0,
+            last_id: FrameId::default(),
+        })
+    }
+
+    fn advance(&mut self, source: &AudioBuffer) {
+        let mut chunk = source.read_since(self.position);
+        self.position = chunk.position;
+
+        if chunk.samples.is_empty() {
+            return;
+        }
+
+        let channels = self.filters.len();
+        for frame in chunk.samples.chunks_exact_mut(channels) {
+            for (sample, filter) in frame.iter_mut().zip(self.filters.iter_mut()) {
+                *sample = filter.process_sample(*sample);
+            }
+        }
+
+        // The filtered samples are presented at the same time as the original.
+        for (samples, time) in chunk.parts() {
+            self.buf.push_at(samples, time);
+        }
+    }
+}
+
+impl Node for AudioFilter {
+    fn has_capability(&self, cap: Capability) -> bool {
+        matches!(cap, Capability::ProvideAudioData)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.input.is_finished()
+    }
+
+    fn provide_audio_data(&mut self, id: FrameId) -> AudioBuffer {
+        if self.last_id.update(id) {
+            let source = self.input.provide_audio_data(id);
+            self.advance(&source);
+        }
+
+        self.buf.clone()
+    }
+}
+
+struct Construct;
+
+impl ConstructNode for Construct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "audio-filter"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        AudioFilter::new(inputs, options, config).map(NodeRef::new)
+    }
+}
+
+pub fn register(factory: &mut NodeFactory) {
+    factory.register(Construct);
+}
@@ -34,6 +34,10 @@ impl Node for Average {
         matches!(cap, Capability::ProvideNumber)
     }
 
+    fn is_finished(&self) -> bool {
+        self.input.is_finished()
+    }
+
     fn provide_number(&mut self, id: FrameId) -> f32 {
         let current = self.input.provide_number(id);
         self.average = (self.alpha * current) + (1.0 - self.alpha) * self.average;
@@ -25,13 +25,17 @@ impl Node for Circle {
         matches!(cap, Capability::ProvideVideoFrame)
     }
 
+    fn is_finished(&self) -> bool {
+        self.input.is_finished()
+    }
+
     fn provide_video_frame(&mut self, id: FrameId, frame: &mut VideoFrame) {
         let radius = self.input.provide_number(id);
 
         let radius = (radius * frame.width().min(frame.height()) as f32) as usize;
         let center = (frame.width() / 2, frame.height() / 2);
 
-        frame.apply(|coords, pixel| {
+        frame.par_apply(|coords, pixel| {
             let intensity = (in_circle_intensity(coords, center, radius) * 255.0) as u8;
             if intensity > 0 {
                 pixel.set_grayscale(intensity)
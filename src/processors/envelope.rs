@@ -0,0 +1,126 @@
+use crate::{
+    options::Options,
+    pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
+    util::{inputs::validate_inputs, video::VideoConfig, Error, FrameId},
+};
+
+// Follows the input with separate attack and release times, like a VU meter.
+// The times are independent of the frame rate.
+#[derive(Debug)]
+pub struct Envelope {
+    input: NodeRef,
+    attack: f32,
+    release: f32,
+    // In frames.
+    hold: usize,
+    // Linear fall per frame instead of the exponential release.
+    peak_decay: Option<f32>,
+    envelope: f32,
+    held: usize,
+}
+
+impl Envelope {
+    pub fn new(inputs: Vec<NodeRef>, options: Options, config: VideoConfig) -> Result<Self, Error> {
+        let input = validate_inputs(inputs, Capability::ProvideNumber)?;
+
+        let get_ms = |name: &str, default: f32| {
+            options
+                .get(name)
+                .unwrap_or(&default.into())
+                .as_f32()
+                .filter(|ms| *ms >= 0.0)
+                .ok_or(Error::InvalidOptions)
+        };
+
+        let fps = config.fps() as f32;
+        let attack = coefficient(get_ms("attack-ms", 10.0)?, fps);
+        let release = coefficient(get_ms("release-ms", 300.0)?, fps);
+        let hold = (get_ms("hold-ms", 0.0)? / 1000.0 * fps).round() as usize;
+
+        // Per second.
+        let peak_decay = options
+            .get("peak-decay")
+            .map(|decay| {
+                decay
+                    .as_f32()
+                    .filter(|decay| *decay > 0.0)
+                    .ok_or(Error::InvalidOptions)
+            })
+            .transpose()?
+            .map(|decay| decay / fps);
+
+        Ok(Self {
+            input,
+            attack,
+            release,
+            hold,
+            peak_decay,
+            envelope: 0.0,
+            held: 0,
+        })
+    }
+}
+
+impl Node for Envelope {
+    fn has_capability(&self, cap: Capability) -> bool {
+        matches!(cap, Capability::ProvideNumber)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.input.is_finished()
+    }
+
+    fn provide_number(&mut self, id: FrameId) -> f32 {
+        let current = self.input.provide_number(id);
+
+        if current >= self.envelope {
+            self.envelope += self.attack * (current - self.envelope);
+            self.held = 0;
+        } else if self.held < self.hold {
+            self.held += 1;
+        } else {
+            self.envelope = match self.peak_decay {
+                Some(decay) => (self.envelope - decay).max(current),
+                None => self.envelope + self.release * (current - self.envelope),
+            };
+        }
+
+        self.envelope
+    }
+}
+
+// Smoothing coefficient of the exponential approach which covers 63 % of the
+// distance in the given time.
+fn coefficient(ms: f32, fps: f32) -> f32 {
+    let frames = ms / 1000.0 * fps;
+
+    if frames > 0.0 {
+        1.0 - (-1.0 / frames).exp()
+    } else {
+        1.0
+    }
+}
+
+struct Construct;
+
+impl ConstructNode for Construct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "envelope"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        Envelope::new(inputs, options, config).map(NodeRef::new)
+    }
+}
+
+pub fn register(factory: &mut NodeFactory) {
+    factory.register(Construct);
+}
@@ -26,13 +26,17 @@ impl Node for Equalizer {
         matches!(cap, Capability::ProvideVideoFrame)
     }
 
+    fn is_finished(&self) -> bool {
+        self.input.is_finished()
+    }
+
     fn provide_video_frame(&mut self, id: FrameId, frame: &mut VideoFrame) {
         let spectrum = self.input.provide_spectrum(id);
         let n_bins = spectrum.len();
         let bin_width = (frame.width() as f32 / n_bins as f32).ceil() as usize;
         let frame_height = frame.height();
 
-        frame.apply(|(x, y), pixel| {
+        frame.par_apply(|(x, y), pixel| {
             let bin = x / bin_width;
             let amplitude = spectrum[bin].norm();
             let bin_height = (amplitude * frame_height as f32).round() as usize;
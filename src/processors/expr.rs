@@ -0,0 +1,143 @@
+use std::time::Instant;
+
+use crate::{
+    options::Options,
+    pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
+    util::{
+        expr::Expr,
+        inputs::{validate_inputs, Many},
+        video::VideoConfig,
+        Error, FrameId,
+    },
+};
+
+// Variables available in addition to the inputs, the time in seconds and the
+// index of the frame.
+const BUILTINS: [&str; 2] = ["t", "frame"];
+
+// Combines the number inputs by an arithmetic expression, e.g.,
+// `0.2 + 0.8 * bass * (1 - treble)`. The inputs are bound to the names in
+// `variables`, in order.
+#[derive(Debug)]
+pub struct ExprNode {
+    inputs: Vec<NodeRef>,
+    expr: Expr,
+    values: Vec<f32>,
+    fps: f32,
+    // Presentation time of the first frame, in real-time mode.
+    start: Option<Instant>,
+    // Number of frames so far, in offline mode.
+    frame: usize,
+}
+
+impl ExprNode {
+    pub fn new(inputs: Vec<NodeRef>, options: Options, config: VideoConfig) -> Result<Self, Error> {
+        let inputs = validate_inputs(inputs, Many(Capability::ProvideNumber))?;
+
+        let source = options
+            .get("expr")
+            .ok_or(Error::InvalidOptions)?
+            .as_str()
+            .ok_or(Error::InvalidOptions)?;
+
+        let variables = match options.get("variables") {
+            Some(variables) => variables
+                .as_slice()
+                .ok_or(Error::InvalidOptions)?
+                .iter()
+                .map(|variable| variable.as_str().ok_or(Error::InvalidOptions))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+
+        if variables.len() != inputs.len() {
+            return Err(Error::InvalidInputs);
+        }
+
+        if variables
+            .iter()
+            .enumerate()
+            .any(|(i, variable)| BUILTINS.contains(variable) || variables[..i].contains(variable))
+        {
+            return Err(Error::InvalidOptions);
+        }
+
+        let names = variables
+            .iter()
+            .copied()
+            .chain(BUILTINS)
+            .collect::<Vec<_>>();
+        let expr = Expr::parse(source, &names).map_err(Error::InvalidExpression)?;
+
+        Ok(Self {
+            inputs,
+            expr,
+            values: vec![0.0; names.len()],
+            fps: config.fps() as f32,
+            start: None,
+            frame: 0,
+        })
+    }
+}
+
+impl Node for ExprNode {
+    fn has_capability(&self, cap: Capability) -> bool {
+        matches!(cap, Capability::ProvideNumber)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.inputs.iter().any(|input| input.is_finished())
+    }
+
+    fn provide_number(&mut self, id: FrameId) -> f32 {
+        let n = self.inputs.len();
+
+        for (value, input) in self.values.iter_mut().zip(self.inputs.iter_mut()) {
+            *value = input.provide_number(id);
+        }
+
+        // In real-time mode, frames might be dropped or repeated, the time
+        // follows the wall clock regardless.
+        let (t, frame) = match id.time() {
+            Some(time) => {
+                let start = *self.start.get_or_insert(time);
+                let t = time.saturating_duration_since(start).as_secs_f32();
+                (t, (t * self.fps).round())
+            }
+            None => {
+                let frame = self.frame as f32;
+                self.frame += 1;
+                (frame / self.fps, frame)
+            }
+        };
+
+        self.values[n] = t;
+        self.values[n + 1] = frame;
+
+        self.expr.eval(&self.values)
+    }
+}
+
+struct Construct;
+
+impl ConstructNode for Construct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "expr"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        ExprNode::new(inputs, options, config).map(NodeRef::new)
+    }
+}
+
+pub fn register(factory: &mut NodeFactory) {
+    factory.register(Construct);
+}
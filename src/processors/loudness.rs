@@ -23,9 +23,13 @@ impl Node for Loudness {
         matches!(cap, Capability::ProvideNumber)
     }
 
+    fn is_finished(&self) -> bool {
+        self.input.is_finished()
+    }
+
     fn provide_number(&mut self, id: FrameId) -> f32 {
         let data = self.input.provide_audio_data(id);
-        let data = data.frames(1);
+        let data = data.frames_until(id, 1);
         let rms = data
             .iter()
             .copied()
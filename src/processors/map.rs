@@ -0,0 +1,259 @@
+use crate::{
+    options::{Options, Value},
+    pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
+    util::{inputs::validate_inputs, video::VideoConfig, Error, FrameId},
+};
+
+// Maps the input from one range to another, optionally through a curve. With
+// auto-gain, the input range follows the running minimum and maximum of the
+// input instead.
+#[derive(Debug)]
+pub struct Map {
+    input: NodeRef,
+    in_range: (f32, f32),
+    out_range: (f32, f32),
+    clamp: bool,
+    curve: Curve,
+    auto_gain: Option<AutoGain>,
+}
+
+impl Map {
+    pub fn new(inputs: Vec<NodeRef>, options: Options, config: VideoConfig) -> Result<Self, Error> {
+        let input = validate_inputs(inputs, Capability::ProvideNumber)?;
+
+        let get_range =
+            |name: &str| match options.get(name).unwrap_or(&(0.0, 1.0).into()).as_slice() {
+                Some([Value::Number(start), Value::Number(end)]) if start != end => {
+                    Ok((*start, *end))
+                }
+                _ => Err(Error::InvalidOptions),
+            };
+
+        let in_range = get_range("in-range")?;
+        let out_range = get_range("out-range")?;
+
+        let clamp = options
+            .get("clamp")
+            .unwrap_or(&true.into())
+            .as_bool()
+            .ok_or(Error::InvalidOptions)?;
+
+        let exponent = options
+            .get("exponent")
+            .unwrap_or(&2.0.into())
+            .as_f32()
+            .filter(|exponent| *exponent > 0.0)
+            .ok_or(Error::InvalidOptions)?;
+
+        let curve = options
+            .get("curve")
+            .map(|curve| Curve::from_value(curve, exponent))
+            .transpose()?
+            .unwrap_or(Curve::Linear);
+
+        let auto_gain = options
+            .get("auto-gain")
+            .unwrap_or(&false.into())
+            .as_bool()
+            .ok_or(Error::InvalidOptions)?;
+
+        // Time in seconds in which the tracked range shrinks to about a third
+        // when the input stops reaching its bounds.
+        let decay = options
+            .get("decay")
+            .unwrap_or(&5.0.into())
+            .as_f32()
+            .filter(|decay| *decay > 0.0)
+            .ok_or(Error::InvalidOptions)?;
+
+        // Below this span of the tracked range, the input is considered
+        // constant, e.g., silence, so that its fluctuations are not stretched to
+        // the whole output range.
+        let min_range = options
+            .get("min-range")
+            .unwrap_or(&0.01.into())
+            .as_f32()
+            .filter(|min_range| *min_range >= 0.0)
+            .ok_or(Error::InvalidOptions)?;
+
+        let auto_gain = auto_gain.then(|| AutoGain::new(decay, config.fps(), min_range));
+
+        Ok(Self {
+            input,
+            in_range,
+            out_range,
+            clamp,
+            curve,
+            auto_gain,
+        })
+    }
+}
+
+impl Node for Map {
+    fn has_capability(&self, cap: Capability) -> bool {
+        matches!(cap, Capability::ProvideNumber)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.input.is_finished()
+    }
+
+    fn provide_number(&mut self, id: FrameId) -> f32 {
+        let current = self.input.provide_number(id);
+
+        let range = match self.auto_gain.as_mut() {
+            Some(auto_gain) => auto_gain.update(current),
+            None => Some(self.in_range),
+        };
+
+        let x = match range {
+            Some((start, end)) if end != start => (current - start) / (end - start),
+            _ => 0.0,
+        };
+        let x = if self.clamp { x.clamp(0.0, 1.0) } else { x };
+
+        let (out_start, out_end) = self.out_range;
+        out_start + self.curve.apply(x) * (out_end - out_start)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Curve {
+    Linear,
+    Log,
+    Exp,
+    Pow(f32),
+}
+
+impl Curve {
+    fn from_value(value: &Value, exponent: f32) -> Result<Self, Error> {
+        match value.as_str() {
+            Some("linear") => Ok(Curve::Linear),
+            Some("log") => Ok(Curve::Log),
+            Some("exp") => Ok(Curve::Exp),
+            Some("pow") => Ok(Curve::Pow(exponent)),
+            _ => Err(Error::InvalidOptions),
+        }
+    }
+
+    // All curves go through (0, 0) and (1, 1).
+    fn apply(&self, x: f32) -> f32 {
+        // Steepness of the log and exp curves.
+        const BASE: f32 = 10.0;
+
+        match self {
+            Curve::Linear => x,
+            Curve::Log => (1.0 + (BASE - 1.0) * x.max(0.0)).log(BASE),
+            Curve::Exp => (BASE.powf(x) - 1.0) / (BASE - 1.0),
+            Curve::Pow(exponent) => x.signum() * x.abs().powf(*exponent),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct AutoGain {
+    rate: f32,
+    min_range: f32,
+    range: Option<(f32, f32)>,
+    // Last range at least as wide as the minimum.
+    wide_range: Option<(f32, f32)>,
+}
+
+impl AutoGain {
+    fn new(decay: f32, fps: usize, min_range: f32) -> Self {
+        Self {
+            rate: 1.0 - (-1.0 / (decay * fps as f32)).exp(),
+            min_range,
+            range: None,
+            wide_range: None,
+        }
+    }
+
+    // The bounds jump to a new extreme immediately and slowly move towards the
+    // input otherwise. While it is narrower than the minimum, e.g., for a
+    // steady input, the last wide enough range is kept. None before there was
+    // any.
+    fn update(&mut self, x: f32) -> Option<(f32, f32)> {
+        let (min, max) = self.range.unwrap_or((x, x));
+        let min = if x < min {
+            x
+        } else {
+            min + self.rate * (x - min)
+        };
+        let max = if x > max {
+            x
+        } else {
+            max + self.rate * (x - max)
+        };
+
+        self.range = Some((min, max));
+
+        if max - min >= self.min_range {
+            self.wide_range = Some((min, max));
+        }
+
+        self.wide_range
+    }
+}
+
+struct Construct;
+
+impl ConstructNode for Construct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "map"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        Map::new(inputs, options, config).map(NodeRef::new)
+    }
+}
+
+pub fn register(factory: &mut NodeFactory) {
+    factory.register(Construct);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_gain_constant_input() {
+        let mut auto_gain = AutoGain::new(1.0, 10, 0.01);
+
+        for i in 0..100 {
+            // Tiny fluctuations, e.g., noise in silence.
+            let x = 0.5 + if i % 2 == 0 { 0.001 } else { -0.001 };
+            assert_eq!(auto_gain.update(x), None);
+        }
+    }
+
+    #[test]
+    fn auto_gain_step() {
+        let mut auto_gain = AutoGain::new(1.0, 10, 0.01);
+
+        for _ in 0..10 {
+            assert_eq!(auto_gain.update(0.0), None);
+        }
+
+        // The maximum jumps to the new level, the minimum follows slowly.
+        let (min, max) = auto_gain.update(1.0).unwrap();
+        assert!(min < 0.1, "{min}");
+        assert_eq!(max, 1.0);
+
+        // Once the range is too narrow, the last wide enough one is kept and
+        // the steady input stays at the top of it.
+        for _ in 0..100 {
+            let (min, max) = auto_gain.update(1.0).unwrap();
+            assert!(max - min >= 0.01, "{min} {max}");
+            assert!((1.0 - min) / (max - min) >= 1.0, "{min} {max}");
+        }
+    }
+}
@@ -66,19 +66,23 @@ impl Node for Merge {
         matches!(cap, Capability::ProvideVideoFrame)
     }
 
+    fn is_finished(&self) -> bool {
+        self.inputs.iter().any(|input| input.is_finished())
+    }
+
     fn provide_video_frame(&mut self, id: FrameId, frame: &mut VideoFrame) {
         self.inputs[0].provide_video_frame(id, frame);
 
         let c1 = self.contributions[0];
 
-        frame.apply(|_, pixel| {
+        frame.par_apply(|_, pixel| {
             pixel.set_red_f(c1 * pixel.red_f());
             pixel.set_green_f(c1 * pixel.green_f());
             pixel.set_blue_f(c1 * pixel.blue_f());
         });
 
         if self.inputs.len() > 1 {
-            let mut frame_copy = frame.clone();
+            let mut frame_copy = VideoFrame::new(frame.width(), frame.height());
             let mode = self.mode;
 
             for (input, c) in self
@@ -89,7 +93,7 @@ impl Node for Merge {
             {
                 input.provide_video_frame(id, &mut frame_copy);
 
-                frame.apply_zip(&mut frame_copy, |_, pixel1, pixel2| {
+                frame.par_apply_zip(&mut frame_copy, |_, pixel1, pixel2| {
                     pixel1.set_red_f(mode.apply(pixel1.red_f(), c * pixel2.red_f()));
                     pixel1.set_green_f(mode.apply(pixel1.green_f(), c * pixel2.green_f()));
                     pixel1.set_blue_f(mode.apply(pixel1.blue_f(), c * pixel2.blue_f()));
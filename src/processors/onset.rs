@@ -0,0 +1,167 @@
+use crate::{
+    options::{Options, Value},
+    pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
+    util::{
+        inputs::validate_inputs,
+        onset::{OnsetDetector, SpectralFlux},
+        spectrum::{Spectrum, SpectrumStore, Stft, Window},
+        video::VideoConfig,
+        Error, FrameId,
+    },
+};
+
+// Short enough to resolve the attack of a drum hit.
+const WINDOW_LEN: usize = 1024;
+
+// Length of the history the threshold adapts to, in seconds.
+const HISTORY: f32 = 1.0;
+
+// Outputs 1.0 on the frame an onset (e.g., a beat) is detected, 0.0 otherwise.
+#[derive(Debug)]
+pub struct Onset {
+    input: Input,
+    band: Option<(f32, f32)>,
+    flux: SpectralFlux,
+    detector: OnsetDetector,
+}
+
+#[derive(Debug)]
+enum Input {
+    Audio(NodeRef, SpectrumStore),
+    Spectrum(NodeRef),
+}
+
+impl Onset {
+    pub fn new(inputs: Vec<NodeRef>, options: Options, config: VideoConfig) -> Result<Self, Error> {
+        let input = validate_inputs(
+            inputs,
+            [Capability::ProvideAudioData, Capability::ProvideSpectrum],
+        )?;
+
+        let mut input = if input.has_capability(Capability::ProvideSpectrum) {
+            Input::Spectrum(input)
+        } else {
+            let spectrum = SpectrumStore::new(Stft::new(WINDOW_LEN, Window::Hann));
+            Input::Audio(input, spectrum)
+        };
+
+        let sensitivity = options
+            .get("sensitivity")
+            .unwrap_or(&0.5.into())
+            .as_f32()
+            .filter(|sensitivity| (0.0..=1.0).contains(sensitivity))
+            .ok_or(Error::InvalidOptions)?;
+
+        let min_interval = options
+            .get("min-interval-ms")
+            .unwrap_or(&100.0.into())
+            .as_f32()
+            .filter(|interval| *interval >= 0.0)
+            .ok_or(Error::InvalidOptions)?;
+
+        // Frequency range in Hz the onsets are detected in, e.g., [40, 120] for
+        // a kick drum.
+        let band = match options.get("band").map(|band| band.as_slice()) {
+            Some(Some([Value::Number(low), Value::Number(high)])) if low < high => {
+                Some((*low, *high))
+            }
+            Some(_) => return Err(Error::InvalidOptions),
+            None => None,
+        };
+
+        // A single bin is too noisy for the flux. Only the bins are needed
+        // here, the default id does not make the input advance.
+        if let Some(band) = band {
+            let spectrum = input.spectrum(FrameId::default());
+            if band_magnitudes(&spectrum, Some(band)).count() < 2 {
+                return Err(Error::InvalidOptions);
+            }
+        }
+
+        let fps = config.fps() as f32;
+        let min_interval = (min_interval / 1000.0 * fps).ceil() as usize;
+        let history_len = (HISTORY * fps).ceil() as usize;
+
+        Ok(Self {
+            input,
+            band,
+            flux: SpectralFlux::new(),
+            detector: OnsetDetector::new(history_len, sensitivity, min_interval),
+        })
+    }
+}
+
+impl Input {
+    fn spectrum(&mut self, id: FrameId) -> Spectrum {
+        match self {
+            Input::Audio(input, spectrum) => {
+                let data = input.provide_audio_data(id);
+                spectrum.compute(id, &data.exact_until(id, WINDOW_LEN), data.sample_rate())
+            }
+            Input::Spectrum(input) => input.provide_spectrum(id),
+        }
+    }
+}
+
+impl Node for Onset {
+    fn has_capability(&self, cap: Capability) -> bool {
+        matches!(cap, Capability::ProvideNumber)
+    }
+
+    fn is_finished(&self) -> bool {
+        match &self.input {
+            Input::Audio(input, _) | Input::Spectrum(input) => input.is_finished(),
+        }
+    }
+
+    fn provide_number(&mut self, id: FrameId) -> f32 {
+        let spectrum = self.input.spectrum(id);
+        let flux = self.flux.compute(band_magnitudes(&spectrum, self.band));
+
+        if self.detector.detect(flux) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+fn band_magnitudes(
+    spectrum: &Spectrum,
+    band: Option<(f32, f32)>,
+) -> impl Iterator<Item = f32> + '_ {
+    spectrum
+        .iter()
+        .enumerate()
+        .filter(move |(bin, _)| match band {
+            Some((low, high)) => {
+                (low..=high).contains(&spectrum.freq(*bin, spectrum.sample_rate()))
+            }
+            None => true,
+        })
+        .map(|(_, x)| x.norm())
+}
+
+struct Construct;
+
+impl ConstructNode for Construct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "onset"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        Onset::new(inputs, options, config).map(NodeRef::new)
+    }
+}
+
+pub fn register(factory: &mut NodeFactory) {
+    factory.register(Construct);
+}
@@ -0,0 +1,125 @@
+use crate::{
+    options::Options,
+    pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
+    util::{
+        audio::AudioBuffer, inputs::validate_inputs, resample::Resampler, video::VideoConfig,
+        Error, FrameId,
+    },
+};
+
+const DEFAULT_SAMPLE_RATE: i32 = 44100;
+
+// Makes the analysis independent of the audio hardware, e.g., the frequency
+// resolution of a spectrum depends on the sample rate.
+#[derive(Debug)]
+pub struct Resample {
+    input: NodeRef,
+    buf: AudioBuffer,
+    resampler: Resampler,
+    samples: Vec<f32>,
+    position: u64,
+    last_id: FrameId,
+}
+
+impl Resample {
+    pub fn new(inputs: Vec<NodeRef>, options: Options, config: VideoConfig) -> Result<Self, Error> {
+        let mut input = validate_inputs(inputs, Capability::ProvideAudioData)?;
+
+        let sample_rate = options
+            .get("sample-rate")
+            .unwrap_or(&DEFAULT_SAMPLE_RATE.into())
+            .as_i32()
+            .filter(|rate| (1000..=192000).contains(rate))
+            .ok_or(Error::InvalidOptions)? as usize;
+
+        // Mono is the mix of all channels.
+        let channels = options
+            .get("channels")
+            .unwrap_or(&1.into())
+            .as_i32()
+            .filter(|channels| (1..=2).contains(channels))
+            .ok_or(Error::InvalidOptions)? as usize;
+
+        // Only the format of the buffer is needed here, the default id does not
+        // make the source advance.
+        let source = input.provide_audio_data(FrameId::default());
+        let resampler = Resampler::new(
+            source.sample_rate(),
+            source.channels(),
+            sample_rate,
+            channels,
+        );
+
+        let buf = AudioBuffer::with_channels(sample_rate, config.fps(), channels)
+            .with_latency(source.latency());
+
+        Ok(Self {
+            input,
+            buf,
+            resampler,
+            samples: Vec::new(),
+            position: 0,
+            last_id: FrameId::default(),
+        })
+    }
+
+    fn advance(&mut self, source: &AudioBuffer) {
+        let chunk = source.read_since(self.position);
+        self.position = chunk.position;
+
+        // Each part is presented at the same time as the original.
+        for (part, time) in chunk.parts() {
+            self.samples.clear();
+            self.resampler.process(part, &mut self.samples);
+
+            // The buffer does not accept arbitrarily large chunks.
+            let frame_size = self.buf.frame_size() * self.buf.channels();
+            for samples in self.samples.chunks(frame_size) {
+                self.buf.push_at(samples, time);
+            }
+        }
+    }
+}
+
+impl Node for Resample {
+    fn has_capability(&self, cap: Capability) -> bool {
+        matches!(cap, Capability::ProvideAudioData)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.input.is_finished()
+    }
+
+    fn provide_audio_data(&mut self, id: FrameId) -> AudioBuffer {
+        if self.last_id.update(id) {
+            let source = self.input.provide_audio_data(id);
+            self.advance(&source);
+        }
+
+        self.buf.clone()
+    }
+}
+
+struct Construct;
+
+impl ConstructNode for Construct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "resample"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        Resample::new(inputs, options, config).map(NodeRef::new)
+    }
+}
+
+pub fn register(factory: &mut NodeFactory) {
+    factory.register(Construct);
+}
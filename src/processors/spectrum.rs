@@ -52,11 +52,15 @@ impl Node for SpectrumNode {
         matches!(cap, Capability::ProvideSpectrum)
     }
 
+    fn is_finished(&self) -> bool {
+        self.input.is_finished()
+    }
+
     fn provide_spectrum(&mut self, id: FrameId) -> Spectrum {
         let data = self.input.provide_audio_data(id);
         let spectrum = self.spectrum.compute(
             id,
-            &data.exact(self.spectrum.window_len()),
+            &data.exact_until(id, self.spectrum.window_len()),
             data.sample_rate(),
         );
         spectrum
@@ -86,3 +90,29 @@ impl ConstructNode for Construct {
 pub fn register(factory: &mut NodeFactory) {
     factory.register(Construct);
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::sources::test_signal::TestSignal;
+
+    use super::*;
+
+    #[test]
+    fn window_longer_than_frame() {
+        // Offline, each frame only adds about 1837 samples at 44.1 kHz.
+        let config = VideoConfig::builder().offline(true).build();
+        let options = [("frequency".to_string(), 440.0.into())]
+            .into_iter()
+            .collect();
+        let sine = TestSignal::new(Vec::new(), options, config).unwrap();
+        let mut node = SpectrumNode::new(vec![NodeRef::new(sine)], Options::new()).unwrap();
+        assert!(node.spectrum.window_len() > 2000);
+
+        let spectrum = node.provide_spectrum(FrameId::new());
+        let peak = (0..spectrum.len())
+            .max_by(|&a, &b| spectrum[a].norm().total_cmp(&spectrum[b].norm()))
+            .unwrap();
+        let freq = spectrum.freq(peak, spectrum.sample_rate());
+        assert!((freq - 440.0).abs() < 20.0, "peak at {} Hz", freq);
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{
+    options::{Options, Value},
+    pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
+    util::{
+        inputs::validate_inputs,
+        onset::SpectralFlux,
+        spectrum::{ComputeSpectrum, Stft, Window},
+        tempo::{Tempo, TempoEstimator},
+        video::VideoConfig,
+        Error, FrameId,
+    },
+};
+
+const WINDOW_LEN: usize = 1024;
+const HOP: usize = 512;
+
+// Length of the onset strength history the tempo is estimated from, in
+// seconds.
+const HISTORY: f32 = 8.0;
+
+// Outputs the estimated tempo in BPM, the phase of the beat as a ramp from 0 to
+// 1, or the confidence of the estimate from 0 to 1. Everything is 0 until
+// there is a periodic enough onset history.
+//
+// The input is either audio or another tempo node, whose estimate is then used
+// instead of analysing the same audio again, e.g., one node for the BPM and
+// another for the phase.
+#[derive(Debug)]
+pub struct TempoNode {
+    input: Input,
+    output: Output,
+}
+
+#[derive(Debug)]
+enum Input {
+    Audio(Tracker),
+    Tempo(NodeRef),
+}
+
+impl TempoNode {
+    pub fn new(inputs: Vec<NodeRef>, options: Options) -> Result<Self, Error> {
+        let input = validate_inputs(
+            inputs,
+            [Capability::ProvideAudioData, Capability::ProvideTempo],
+        )?;
+
+        let output = options
+            .get("output")
+            .map(Output::from_value)
+            .transpose()?
+            .unwrap_or(Output::Bpm);
+
+        if input.has_capability(Capability::ProvideTempo) {
+            // The range is given to the node tracking the tempo.
+            if options.contains_key("bpm-range") {
+                return Err(Error::InvalidOptions);
+            }
+
+            return Ok(Self {
+                input: Input::Tempo(input),
+                output,
+            });
+        }
+
+        let default_bpm_range = (60.0, 180.0).into();
+        let bpm_range = options
+            .get("bpm-range")
+            .unwrap_or(&default_bpm_range)
+            .as_slice()
+            .ok_or(Error::InvalidOptions)?;
+
+        let bpm_range = match bpm_range {
+            [Value::Number(bpm_min), Value::Number(bpm_max)]
+                if *bpm_min > 0.0 && bpm_min < bpm_max =>
+            {
+                (*bpm_min, *bpm_max)
+            }
+            _ => return Err(Error::InvalidOptions),
+        };
+
+        Ok(Self {
+            input: Input::Audio(Tracker::new(input, bpm_range)),
+            output,
+        })
+    }
+}
+
+impl Node for TempoNode {
+    fn has_capability(&self, cap: Capability) -> bool {
+        matches!(cap, Capability::ProvideNumber | Capability::ProvideTempo)
+    }
+
+    fn is_finished(&self) -> bool {
+        match &self.input {
+            Input::Audio(tracker) => tracker.input.is_finished(),
+            Input::Tempo(input) => input.is_finished(),
+        }
+    }
+
+    fn provide_tempo(&mut self, id: FrameId) -> Tempo {
+        match &mut self.input {
+            Input::Audio(tracker) => tracker.update(id),
+            Input::Tempo(input) => input.provide_tempo(id),
+        }
+    }
+
+    fn provide_number(&mut self, id: FrameId) -> f32 {
+        let tempo = self.provide_tempo(id);
+
+        match self.output {
+            Output::Bpm => tempo.bpm,
+            Output::Phase => tempo.phase,
+            Output::Confidence => tempo.confidence,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Tracker {
+    input: NodeRef,
+    stft: Stft,
+    flux: SpectralFlux,
+    estimator: TempoEstimator,
+    channels: usize,
+    position: u64,
+    pending: Vec<f32>,
+    // The estimate is updated once per frame, both outputs of the node use it.
+    tempo: Tempo,
+    last_id: FrameId,
+}
+
+impl Tracker {
+    fn new(mut input: NodeRef, bpm_range: (f32, f32)) -> Self {
+        // Only the format of the buffer is needed here, the default id does not
+        // make the source advance.
+        let source = input.provide_audio_data(FrameId::default());
+        let rate = source.sample_rate() as f32 / HOP as f32;
+
+        Self {
+            input,
+            stft: Stft::new(WINDOW_LEN, Window::Hann),
+            flux: SpectralFlux::new(),
+            estimator: TempoEstimator::new(rate, HISTORY, bpm_range),
+            channels: source.channels(),
+            position: 0,
+            pending: Vec::new(),
+            tempo: Tempo::default(),
+            last_id: FrameId::default(),
+        }
+    }
+
+    fn update(&mut self, id: FrameId) -> Tempo {
+        if self.last_id.update(id) {
+            self.advance(id);
+
+            self.tempo = self.estimator.estimate().unwrap_or_default();
+        }
+
+        self.tempo
+    }
+
+    // Feeds the onset strength of the new samples into the estimator.
+    fn advance(&mut self, id: FrameId) {
+        let source = self.input.provide_audio_data(id);
+        let chunk = source.read_since(self.position);
+        self.position = chunk.position;
+
+        let channels = self.channels;
+        self.pending.extend(
+            chunk
+                .samples
+                .chunks_exact(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+
+        let mut start = 0;
+        while self.pending.len() - start >= WINDOW_LEN {
+            let spectrum = self.stft.compute(&self.pending[start..start + WINDOW_LEN]);
+            let strength = self.flux.compute(spectrum.iter().map(|x| x.norm()));
+            self.estimator.push(strength);
+            start += HOP;
+        }
+
+        self.pending.drain(..start);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Bpm,
+    Phase,
+    Confidence,
+}
+
+impl Output {
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        match value.as_str() {
+            Some("bpm") => Ok(Output::Bpm),
+            Some("phase") => Ok(Output::Phase),
+            Some("confidence") => Ok(Output::Confidence),
+            _ => Err(Error::InvalidOptions),
+        }
+    }
+}
+
+struct Construct;
+
+impl ConstructNode for Construct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "tempo"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        _: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        TempoNode::new(inputs, options).map(NodeRef::new)
+    }
+}
+
+pub fn register(factory: &mut NodeFactory) {
+    factory.register(Construct);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sources::test_signal::TestSignal;
+
+    use super::*;
+
+    fn options(options: &[(&str, Value)]) -> Options {
+        options
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn tracks_clicks() {
+        let config = VideoConfig::builder().offline(true).build();
+        let clicks = TestSignal::new(
+            Vec::new(),
+            options(&[
+                ("waveform", "clicks".to_string().into()),
+                ("frequency", 100.0.into()),
+                ("bpm", 120.0.into()),
+            ]),
+            config,
+        )
+        .unwrap();
+        let input = NodeRef::new(clicks);
+
+        let output = |output: &str| options(&[("output", output.to_string().into())]);
+        let mut bpm = NodeRef::new(TempoNode::new(vec![input], output("bpm")).unwrap());
+        let mut phase = TempoNode::new(vec![bpm.clone()], output("phase")).unwrap();
+
+        // The phase comes from the tracker of the BPM node.
+        assert!(matches!(phase.input, Input::Tempo(_)));
+        assert!(TempoNode::new(
+            vec![bpm.clone()],
+            options(&[("bpm-range", (80.0, 160.0).into())])
+        )
+        .is_err());
+
+        let mut estimate = (0.0, 0.0);
+        for _ in 0..(10 * config.fps()) {
+            let id = FrameId::new();
+            estimate = (bpm.provide_number(id), phase.provide_number(id));
+        }
+
+        let (bpm, phase) = estimate;
+        assert!((bpm - 120.0).abs() < 2.0, "{bpm}");
+        assert!((0.0..=1.0).contains(&phase), "{phase}");
+    }
+}
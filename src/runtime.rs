@@ -0,0 +1,143 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    thread,
+};
+
+use crossbeam_channel::{select, Receiver, Select};
+
+use crate::{
+    pipeline::{
+        scheduler::{Scheduler, SinkPort, SinkStats},
+        Node, Pipeline,
+    },
+    util::Error,
+};
+
+enum Event {
+    Finished(Result<(), Error>),
+    Interrupted,
+}
+
+// Starts the scheduler and all sinks, each in its own thread, and blocks until
+// the sinks are finished. When any sink finishes (successfully or with an
+// error) or something is received from `interrupts`, e.g., sent by a Ctrl-C
+// handler, the scheduler and all remaining sinks are stopped. Pass
+// `crossbeam_channel::never()` if the run can't be interrupted. The first
+// error, if any, is returned, otherwise the frame statistics of each sink.
+// Events of the nodes, e.g., a disconnected audio device, are printed to stderr
+// meanwhile.
+pub fn run(pipeline: Pipeline, interrupts: Receiver<()>) -> Result<Vec<Arc<SinkStats>>, Error> {
+    let (sender, receiver) = crossbeam_channel::unbounded();
+
+    let events = pipeline
+        .levels()
+        .iter()
+        .flatten()
+        .chain(pipeline.sinks())
+        .filter_map(Node::events)
+        .collect::<Vec<_>>();
+
+    if !events.is_empty() {
+        thread::spawn(move || report(events));
+    }
+
+    let sinks = pipeline.sinks().to_vec();
+    let ports = sinks
+        .iter()
+        .map(|sink| sink.sink_port())
+        .collect::<Vec<_>>();
+
+    let scheduler = Scheduler::new(pipeline.levels().to_vec(), ports.clone(), pipeline.config());
+    let scheduler_stop_handle = scheduler.stop_handle();
+    let scheduler_thread = thread::spawn(move || scheduler.run());
+
+    let stop_handles = sinks
+        .iter()
+        .map(|sink| sink.stop_handle())
+        .collect::<Vec<_>>();
+
+    let threads = sinks
+        .into_iter()
+        .map(|mut sink| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(|| sink.start()))
+                    .unwrap_or(Err(Error::System));
+                _ = sender.send(Event::Finished(result));
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut running = threads.len();
+    let mut result = Ok(());
+
+    if running > 0 {
+        if let Some(Event::Finished(finished)) = next(&receiver, &interrupts) {
+            running -= 1;
+            result = finished;
+        }
+
+        // The scheduler goes first, in offline mode it might block the sinks
+        // otherwise.
+        scheduler_stop_handle.stop();
+
+        for handle in stop_handles.iter() {
+            handle.stop();
+        }
+    }
+
+    while running > 0 {
+        match next(&receiver, &interrupts) {
+            Some(Event::Finished(finished)) => {
+                running -= 1;
+                result = result.and(finished);
+            }
+            // Repeated interrupt, the sinks are probably stuck. Give up
+            // waiting for them.
+            Some(Event::Interrupted) | None => return result.map(|_| stats(&ports)),
+        }
+    }
+
+    scheduler_stop_handle.stop();
+    result = result.and(scheduler_thread.join().unwrap_or(Err(Error::System)));
+
+    for thread in threads {
+        _ = thread.join();
+    }
+
+    result.map(|_| stats(&ports))
+}
+
+fn next(receiver: &Receiver<Event>, interrupts: &Receiver<()>) -> Option<Event> {
+    select! {
+        recv(receiver) -> event => event.ok(),
+        recv(interrupts) -> _ => Some(Event::Interrupted),
+    }
+}
+
+// Prints the events of the nodes as they come, until all nodes are dropped.
+fn report(events: Vec<Receiver<String>>) {
+    let mut select = Select::new();
+    for receiver in events.iter() {
+        select.recv(receiver);
+    }
+
+    let mut open = events.len();
+    while open > 0 {
+        let operation = select.select();
+        let index = operation.index();
+
+        match operation.recv(&events[index]) {
+            Ok(message) => eprintln!("{}", message),
+            Err(_) => {
+                select.remove(index);
+                open -= 1;
+            }
+        }
+    }
+}
+
+fn stats(ports: &[SinkPort]) -> Vec<Arc<SinkStats>> {
+    ports.iter().map(SinkPort::stats).collect()
+}
@@ -1 +1,4 @@
+pub mod appsrc;
+pub mod codec;
+pub mod file;
 pub mod rtsp;
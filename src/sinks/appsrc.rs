@@ -0,0 +1,147 @@
+use crate::{
+    pipeline::scheduler::{Frame, FrameReceiver},
+    util::{video::VideoConfig, Error},
+};
+
+// Pushes the frames delivered by the scheduler as BGRx buffers into the video
+// appsrc and the audio up to each frame into the audio appsrc. The audio is
+// timestamped on its sample clock, aligned such that the samples of the first
+// frame start with it. When there are no more frames, the end of stream is
+// signalled downstream.
+pub fn feed(
+    video_source: &gst_app::AppSrc,
+    audio_source: Option<&gst_app::AppSrc>,
+    mut frames: FrameReceiver,
+    config: VideoConfig,
+) -> Result<(), Error> {
+    let video_info = gst_video::VideoInfo::builder(
+        gst_video::VideoFormat::Bgrx,
+        config.width() as u32,
+        config.height() as u32,
+    )
+    .fps(gst::Fraction::new(config.fps() as i32, 1))
+    .build()
+    .map_err(|_| Error::System)?;
+
+    video_source.set_format(gst::Format::Time);
+    video_source.set_caps(Some(&video_info.to_caps().map_err(|_| Error::System)?));
+
+    let mut audio = audio_source.map(|source| {
+        source.set_format(gst::Format::Time);
+        AudioFeed {
+            source: source.clone(),
+            position: 0,
+            origin: None,
+        }
+    });
+
+    let mut i = 0;
+    video_source.set_callbacks(
+        gst_app::AppSrcCallbacks::builder()
+            .need_data(move |source, _| {
+                let frame = match frames.next() {
+                    Some(frame) => frame,
+                    None => {
+                        _ = source.end_of_stream();
+
+                        if let Some(audio) = audio.as_ref() {
+                            _ = audio.source.end_of_stream();
+                        }
+
+                        return;
+                    }
+                };
+
+                let mut buffer = gst::Buffer::with_size(video_info.size()).unwrap();
+                {
+                    let buffer_ref = buffer.get_mut().unwrap();
+                    buffer_ref.set_pts(frame_time(i, config));
+                    buffer_ref.set_duration(frame_time(i + 1, config) - frame_time(i, config));
+                    buffer_ref.copy_from_slice(0, frame.video.buf()).unwrap();
+                };
+                _ = source.push_buffer(buffer);
+
+                if let Some(audio) = audio.as_mut() {
+                    audio.push(&frame);
+                }
+
+                i += 1;
+            })
+            .build(),
+    );
+
+    Ok(())
+}
+
+#[derive(Debug)]
+struct AudioFeed {
+    source: gst_app::AppSrc,
+    // Sample clock position up to which the samples were pushed.
+    position: u64,
+    // Sample clock position corresponding to the time 0, may be negative if the
+    // buffer was not filled yet at the first frame.
+    origin: Option<i64>,
+}
+
+impl AudioFeed {
+    fn push(&mut self, frame: &Frame) {
+        let audio = match frame.audio.as_ref() {
+            Some(audio) => audio,
+            None => return,
+        };
+        let buffer = &audio.buffer;
+
+        let origin = match self.origin {
+            Some(origin) => origin,
+            None => {
+                let caps = gst::Caps::builder("audio/x-raw")
+                    .field("format", "F32LE")
+                    .field("layout", "interleaved")
+                    .field("channels", buffer.channels() as i32)
+                    .field("rate", buffer.sample_rate() as i32)
+                    .build();
+                self.source.set_caps(Some(&caps));
+
+                // The first frame comes with the samples of one frame, as every
+                // following one.
+                let origin = audio.position as i64 - buffer.frame_size() as i64;
+                self.position = origin.max(0) as u64;
+                self.origin = Some(origin);
+                origin
+            }
+        };
+
+        // The estimated position may go slightly back, but nothing is pushed
+        // twice.
+        let chunk = buffer.read_range(self.position, audio.position);
+        self.position = self.position.max(chunk.position);
+
+        if chunk.samples.is_empty() {
+            return;
+        }
+
+        let len = (chunk.samples.len() / buffer.channels()) as u64;
+        let start = (chunk.position - len) as i64 - origin;
+        let sample_time = |i: i64| {
+            gst::ClockTime::from_nseconds(i as u64 * 1_000_000_000 / buffer.sample_rate() as u64)
+        };
+
+        let bytes = chunk
+            .samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        let mut gst_buffer = gst::Buffer::from_mut_slice(bytes);
+        {
+            let buffer_ref = gst_buffer.get_mut().unwrap();
+            buffer_ref.set_pts(sample_time(start));
+            buffer_ref.set_duration(sample_time(start + len as i64) - sample_time(start));
+        }
+        _ = self.source.push_buffer(gst_buffer);
+    }
+}
+
+fn frame_time(i: u64, config: VideoConfig) -> gst::ClockTime {
+    gst::ClockTime::from_nseconds(i * 1_000_000_000 / config.fps() as u64)
+}
@@ -0,0 +1,123 @@
+use crate::{
+    options::{Options, Value},
+    util::Error,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    H264,
+    H265,
+    Vp8,
+    Vp9,
+    Mjpeg,
+}
+
+impl Codec {
+    pub fn from_value(value: &Value) -> Result<Self, Error> {
+        match value.as_str() {
+            Some("h264") => Ok(Codec::H264),
+            Some("h265") => Ok(Codec::H265),
+            Some("vp8") => Ok(Codec::Vp8),
+            Some("vp9") => Ok(Codec::Vp9),
+            Some("mjpeg") => Ok(Codec::Mjpeg),
+            _ => Err(Error::InvalidOptions),
+        }
+    }
+
+    // Returns the launch string fragment encoding raw I420 video.
+    pub fn encoder(&self, settings: &EncoderSettings) -> Result<String, Error> {
+        let low_latency = settings.low_latency;
+
+        let mut encoder = match self {
+            Codec::H264 if low_latency => {
+                String::from("x264enc speed-preset=ultrafast tune=zerolatency")
+            }
+            Codec::H264 => String::from("x264enc speed-preset=veryfast"),
+            Codec::H265 if low_latency => {
+                String::from("x265enc speed-preset=ultrafast tune=zerolatency")
+            }
+            Codec::H265 => String::from("x265enc speed-preset=veryfast"),
+            Codec::Vp8 if low_latency => String::from("vp8enc deadline=1"),
+            Codec::Vp8 => String::from("vp8enc"),
+            Codec::Vp9 if low_latency => String::from("vp9enc deadline=1"),
+            Codec::Vp9 => String::from("vp9enc"),
+            Codec::Mjpeg => String::from("jpegenc"),
+        };
+
+        if let Some(bitrate) = settings.bitrate {
+            match self {
+                Codec::H264 | Codec::H265 => encoder.push_str(&format!(" bitrate={bitrate}")),
+                // VPx encoders expect bit/s.
+                Codec::Vp8 | Codec::Vp9 => {
+                    encoder.push_str(&format!(" target-bitrate={}", bitrate * 1000))
+                }
+                // JPEG encoder is controlled by quality only.
+                Codec::Mjpeg => return Err(Error::InvalidOptions),
+            }
+        }
+
+        if let Some(interval) = settings.keyframe_interval {
+            match self {
+                Codec::H264 | Codec::H265 => encoder.push_str(&format!(" key-int-max={interval}")),
+                Codec::Vp8 | Codec::Vp9 => {
+                    encoder.push_str(&format!(" keyframe-max-dist={interval}"))
+                }
+                // Every frame is a keyframe.
+                Codec::Mjpeg => return Err(Error::InvalidOptions),
+            }
+        }
+
+        match self {
+            Codec::H264 => encoder.push_str(" ! h264parse"),
+            Codec::H265 => encoder.push_str(" ! h265parse"),
+            Codec::Vp8 | Codec::Vp9 | Codec::Mjpeg => {}
+        }
+
+        Ok(encoder)
+    }
+
+    pub fn payloader(&self) -> &'static str {
+        match self {
+            Codec::H264 => "rtph264pay",
+            Codec::H265 => "rtph265pay",
+            Codec::Vp8 => "rtpvp8pay",
+            Codec::Vp9 => "rtpvp9pay",
+            Codec::Mjpeg => "rtpjpegpay",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EncoderSettings {
+    // In kbit/s.
+    pub bitrate: Option<u32>,
+    // In frames.
+    pub keyframe_interval: Option<u32>,
+    pub low_latency: bool,
+}
+
+impl EncoderSettings {
+    pub fn from_options(options: &Options, low_latency: bool) -> Result<Self, Error> {
+        let bitrate = get_positive(options, "bitrate")?;
+        let keyframe_interval = get_positive(options, "keyframe-interval")?;
+
+        Ok(Self {
+            bitrate,
+            keyframe_interval,
+            low_latency,
+        })
+    }
+}
+
+fn get_positive(options: &Options, name: &str) -> Result<Option<u32>, Error> {
+    options
+        .get(name)
+        .map(|value| {
+            value
+                .as_i32()
+                .filter(|value| *value > 0)
+                .map(|value| value as u32)
+                .ok_or(Error::InvalidOptions)
+        })
+        .transpose()
+}
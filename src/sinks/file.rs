@@ -0,0 +1,268 @@
+use std::path::Path;
+
+use gst::prelude::*;
+
+use crate::{
+    options::{Options, Value},
+    pipeline::{
+        scheduler::{SinkInputs, SinkPort},
+        ConstructNode, Node, NodeFactory, NodeRef, StopHandle,
+    },
+    util::{video::VideoConfig, Error},
+};
+
+use super::{
+    appsrc,
+    codec::{Codec, EncoderSettings},
+};
+
+#[derive(Debug)]
+pub struct FileSink {
+    pipeline: gst::Pipeline,
+    source: gst_app::AppSrc,
+    audio_source: Option<gst_app::AppSrc>,
+    port: SinkPort,
+}
+
+impl FileSink {
+    pub fn new(inputs: Vec<NodeRef>, options: Options, config: VideoConfig) -> Result<Self, Error> {
+        let port = SinkPort::new(SinkInputs::validate(inputs)?, config);
+
+        let path = options
+            .get("path")
+            .ok_or(Error::InvalidOptions)?
+            .as_str()
+            .ok_or(Error::InvalidOptions)?;
+
+        let container = options
+            .get("container")
+            .map(Container::from_value)
+            .transpose()?
+            .or_else(|| Container::from_path(path))
+            .unwrap_or(Container::Mp4);
+
+        let codec = options
+            .get("codec")
+            .map(Codec::from_value)
+            .transpose()?
+            .unwrap_or_else(|| container.default_codec());
+
+        if !container.supports(codec) {
+            return Err(Error::InvalidOptions);
+        }
+
+        let settings = EncoderSettings::from_options(&options, false)?;
+
+        gst::init().map_err(|_| Error::System)?;
+
+        let audio_branch = if port.has_audio() {
+            format!(
+                "appsrc name=audio-source ! audioconvert ! audioresample ! {} ! mux.",
+                container.audio_encoder()
+            )
+        } else {
+            String::new()
+        };
+
+        let pipeline = gst::parse_launch(&format!(
+            "appsrc name=source ! videoconvert ! video/x-raw,format=I420 ! {} ! mux. {} {} name=mux ! filesink name=sink",
+            codec.encoder(&settings)?,
+            audio_branch,
+            container.muxer(),
+        ))
+        .map_err(|_| Error::System)?
+        .dynamic_cast::<gst::Pipeline>()
+        .map_err(|_| Error::System)?;
+
+        let sink = pipeline.by_name("sink").ok_or(Error::System)?;
+        sink.set_property("location", path);
+        // In offline mode, the frames are written as fast as they are rendered.
+        sink.set_property("sync", !config.is_offline());
+
+        let source = pipeline
+            .by_name("source")
+            .ok_or(Error::System)?
+            .dynamic_cast::<gst_app::AppSrc>()
+            .map_err(|_| Error::System)?;
+
+        let audio_source = port
+            .has_audio()
+            .then(|| {
+                pipeline
+                    .by_name("audio-source")
+                    .ok_or(Error::System)?
+                    .dynamic_cast::<gst_app::AppSrc>()
+                    .map_err(|_| Error::System)
+            })
+            .transpose()?;
+
+        appsrc::feed(&source, audio_source.as_ref(), port.subscribe(), config)?;
+
+        Ok(Self {
+            pipeline,
+            source,
+            audio_source,
+            port,
+        })
+    }
+
+    pub fn start(&self) -> Result<(), Error> {
+        self.pipeline
+            .set_state(gst::State::Playing)
+            .map_err(|_| Error::System)?;
+
+        let bus = self.pipeline.bus().ok_or(Error::System)?;
+        let result = bus
+            .iter_timed(gst::ClockTime::NONE)
+            .find_map(|message| match message.view() {
+                gst::MessageView::Eos(_) => Some(Ok(())),
+                gst::MessageView::Error(_) => Some(Err(Error::System)),
+                _ => None,
+            })
+            .unwrap_or(Ok(()));
+
+        _ = self.pipeline.set_state(gst::State::Null);
+
+        result
+    }
+
+    // Signals the end of stream so that the muxer can finalize the file. The
+    // running `start` returns once the file is written.
+    pub fn stop(&self) {
+        end_of_stream(&self.source, self.audio_source.as_ref());
+    }
+}
+
+// The muxer waits for all of its inputs to end.
+fn end_of_stream(source: &gst_app::AppSrc, audio_source: Option<&gst_app::AppSrc>) {
+    _ = source.end_of_stream();
+
+    if let Some(audio_source) = audio_source {
+        _ = audio_source.end_of_stream();
+    }
+}
+
+impl Drop for FileSink {
+    fn drop(&mut self) {
+        if self.pipeline.current_state() == gst::State::Playing {
+            // Without end of stream, the file would be unreadable for most
+            // containers.
+            self.stop();
+
+            if let Some(bus) = self.pipeline.bus() {
+                _ = bus.timed_pop_filtered(
+                    gst::ClockTime::from_seconds(5),
+                    &[gst::MessageType::Eos, gst::MessageType::Error],
+                );
+            }
+        }
+
+        _ = self.pipeline.set_state(gst::State::Null);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Mp4,
+    Mkv,
+    WebM,
+}
+
+impl Container {
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        match value.as_str() {
+            Some("mp4") => Ok(Container::Mp4),
+            Some("mkv") => Ok(Container::Mkv),
+            Some("webm") => Ok(Container::WebM),
+            _ => Err(Error::InvalidOptions),
+        }
+    }
+
+    fn from_path(path: &str) -> Option<Self> {
+        match Path::new(path).extension()?.to_str()? {
+            "mp4" => Some(Container::Mp4),
+            "mkv" => Some(Container::Mkv),
+            "webm" => Some(Container::WebM),
+            _ => None,
+        }
+    }
+
+    fn default_codec(&self) -> Codec {
+        match self {
+            Container::Mp4 | Container::Mkv => Codec::H264,
+            Container::WebM => Codec::Vp8,
+        }
+    }
+
+    fn supports(&self, codec: Codec) -> bool {
+        match self {
+            Container::Mp4 => matches!(codec, Codec::H264 | Codec::H265),
+            Container::Mkv => true,
+            Container::WebM => matches!(codec, Codec::Vp8 | Codec::Vp9),
+        }
+    }
+
+    fn audio_encoder(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "avenc_aac ! aacparse",
+            Container::Mkv | Container::WebM => "opusenc",
+        }
+    }
+
+    fn muxer(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4mux",
+            Container::Mkv => "matroskamux",
+            Container::WebM => "webmmux",
+        }
+    }
+}
+
+impl Node for FileSink {
+    fn is_sink(&self) -> bool {
+        true
+    }
+
+    fn start(&mut self) -> Result<(), Error> {
+        (*self).start()
+    }
+
+    fn stop_handle(&self) -> StopHandle {
+        let source = self.source.clone();
+        let audio_source = self.audio_source.clone();
+        StopHandle::new(move || end_of_stream(&source, audio_source.as_ref()))
+    }
+
+    fn sink_port(&self) -> SinkPort {
+        self.port.clone()
+    }
+}
+
+struct Construct;
+
+impl ConstructNode for Construct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        // Plain "file" is taken by the audio file source.
+        "video-file"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        FileSink::new(inputs, options, config).map(NodeRef::new)
+    }
+
+    fn is_sink(&self) -> bool {
+        true
+    }
+}
+
+pub fn register(factory: &mut NodeFactory) {
+    factory.register(Construct);
+}
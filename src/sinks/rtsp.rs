@@ -1,58 +1,128 @@
+use std::net::IpAddr;
+
 use gst_rtsp_server::prelude::*;
 
 use gstreamer_rtsp_server::traits::RTSPServerExt;
 
 use crate::{
     options::Options,
-    pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
-    util::{
-        inputs::validate_inputs,
-        video::{VideoConfig, VideoFrame},
-        Error, FrameId,
+    pipeline::{
+        scheduler::{SinkInputs, SinkPort},
+        ConstructNode, Node, NodeFactory, NodeRef, StopHandle,
     },
+    util::{video::VideoConfig, Error},
+};
+
+use super::{
+    appsrc,
+    codec::{Codec, EncoderSettings},
 };
 
-const MOUNT_PATH: &str = "/picasound";
+const DEFAULT_PORT: i32 = 8554;
+const DEFAULT_ADDRESS: &str = "0.0.0.0";
+const DEFAULT_MOUNT_PATH: &str = "/picasound";
 
 #[derive(Debug)]
 pub struct RtspSink {
+    context: glib::MainContext,
     main_loop: glib::MainLoop,
     server: gst_rtsp_server::RTSPServer,
     id: Option<glib::SourceId>,
+    address: IpAddr,
+    mount: String,
+    port: SinkPort,
 }
 
 impl RtspSink {
-    pub fn new(inputs: Vec<NodeRef>, config: VideoConfig) -> Result<Self, Error> {
-        let input = validate_inputs(inputs, Capability::ProvideVideoFrame)?;
+    pub fn new(inputs: Vec<NodeRef>, options: Options, config: VideoConfig) -> Result<Self, Error> {
+        let sink_port = SinkPort::new(SinkInputs::validate(inputs)?, config);
+
+        let port = options
+            .get("port")
+            .unwrap_or(&DEFAULT_PORT.into())
+            .as_i32()
+            .filter(|port| (1..=65535).contains(port))
+            .ok_or(Error::InvalidOptions)?;
+
+        let address = options
+            .get("address")
+            .unwrap_or(&DEFAULT_ADDRESS.to_string().into())
+            .as_str()
+            .and_then(|address| address.parse::<IpAddr>().ok())
+            .ok_or(Error::InvalidOptions)?;
+
+        let mount = options
+            .get("mount")
+            .unwrap_or(&DEFAULT_MOUNT_PATH.to_string().into())
+            .as_str()
+            .filter(|mount| mount.starts_with('/'))
+            .ok_or(Error::InvalidOptions)?
+            .to_string();
+
+        let codec = options
+            .get("codec")
+            .map(Codec::from_value)
+            .transpose()?
+            .unwrap_or(Codec::H264);
+
+        let settings = EncoderSettings::from_options(&options, true)?;
+
+        let shared = options
+            .get("shared")
+            .unwrap_or(&true.into())
+            .as_bool()
+            .ok_or(Error::InvalidOptions)?;
+
+        let encoder = codec.encoder(&settings)?;
 
         gst::init().map_err(|_| Error::System)?;
 
-        let main_loop = glib::MainLoop::new(None, false);
+        // Each sink has its own context so that multiple sinks can run their
+        // main loops in parallel.
+        let context = glib::MainContext::new();
+        let main_loop = glib::MainLoop::new(Some(&context), false);
         let server = gst_rtsp_server::RTSPServer::new();
+        server.set_address(&address.to_string());
+        server.set_service(&port.to_string());
+
         let mounts = server.mount_points().ok_or(Error::System)?;
 
-        let factory = setup_factory(input, config);
+        let factory = setup_factory(sink_port.clone(), config, &encoder, codec.payloader());
+        factory.set_shared(shared);
 
-        mounts.add_factory(MOUNT_PATH, &factory);
+        mounts.add_factory(&mount, &factory);
 
-        let id = Some(server.attach(None).map_err(|_| Error::System)?);
+        let id = Some(server.attach(Some(&context)).map_err(|_| Error::System)?);
 
         Ok(Self {
+            context,
             main_loop,
             server,
             id,
+            address,
+            mount,
+            port: sink_port,
         })
     }
 
+    // Runs until stopped or until the scheduler is finished.
     pub fn start(&self) {
+        self.port.on_close(self.stop_handle());
         self.main_loop.run();
     }
 
     pub fn uri(&self) -> String {
+        let address = if self.address.is_unspecified() {
+            String::from("127.0.0.1")
+        } else {
+            self.address.to_string()
+        };
+
         format!(
-            "rtsp://127.0.0.1:{}{}",
+            "rtsp://{}:{}{}",
+            address,
             self.server.bound_port(),
-            MOUNT_PATH
+            self.mount
         )
     }
 }
@@ -60,7 +130,11 @@ impl RtspSink {
 impl Drop for RtspSink {
     fn drop(&mut self) {
         self.main_loop.quit();
-        self.id.take().unwrap().remove();
+
+        // `SourceId::remove` works only for the default context.
+        if let Some(source) = self.context.find_source_by_id(&self.id.take().unwrap()) {
+            source.destroy();
+        }
     }
 }
 
@@ -70,61 +144,44 @@ impl Drop for RtspSink {
 
 // play with `gst-launch-1.0 rtspsrc location=rtsp://localhost:8554/test latency=0 ! decodebin ! autovideosink`
 
-fn setup_factory(input: NodeRef, config: VideoConfig) -> gst_rtsp_server::RTSPMediaFactory {
+fn setup_factory(
+    port: SinkPort,
+    config: VideoConfig,
+    encoder: &str,
+    payloader: &str,
+) -> gst_rtsp_server::RTSPMediaFactory {
+    let audio_branch = if port.has_audio() {
+        "appsrc name=audio-source ! audioconvert ! audioresample ! opusenc ! rtpopuspay name=pay1 pt=97"
+    } else {
+        ""
+    };
+
     let factory = gst_rtsp_server::RTSPMediaFactory::new();
     factory
-        .set_launch("( appsrc name=source ! videoconvert ! video/x-raw,format=I420 ! x264enc speed-preset=ultrafast tune=zerolatency ! rtph264pay name=pay0 pt=96 )");
-    factory.set_shared(true);
+        .set_launch(&format!("( appsrc name=source ! videoconvert ! video/x-raw,format=I420 ! {encoder} ! {payloader} name=pay0 pt=96 {audio_branch} )"));
 
     factory.connect_closure(
         "media-configure",
         true,
         glib::closure!(|_: &gst_rtsp_server::RTSPMediaFactory,
                         media: &gst_rtsp_server::RTSPMedia| {
-            let mut input = input.clone();
+            let bin = media.element().unwrap().dynamic_cast::<gst::Bin>().unwrap();
 
-            let element = media.element().unwrap();
-            let source = element
-                .dynamic_cast::<gst::Bin>()
-                .unwrap()
+            let source = bin
                 .by_name_recurse_up("source")
                 .unwrap()
                 .dynamic_cast::<gst_app::AppSrc>()
                 .unwrap();
 
-            let video_info = gst_video::VideoInfo::builder(
-                gst_video::VideoFormat::Bgrx,
-                config.width() as u32,
-                config.height() as u32,
-            )
-            .fps(gst::Fraction::new(config.fps() as i32, 1))
-            .build()
-            .unwrap();
-
-            source.set_format(gst::Format::Time);
-            source.set_caps(Some(&video_info.to_caps().unwrap()));
-
-            let mut frame = VideoFrame::new(config.width(), config.height());
-
-            let mut i = 0;
-            source.set_callbacks(
-                gst_app::AppSrcCallbacks::builder()
-                    .need_data(move |source, _| {
-                        frame.clear();
-                        input.provide_video_frame(FrameId::new(), &mut frame);
-
-                        let mut buffer = gst::Buffer::with_size(video_info.size()).unwrap();
-                        {
-                            let buffer_ref = buffer.get_mut().unwrap();
-                            let clock_time = i * (1000.0 / config.fps() as f32).round() as u64;
-                            buffer_ref.set_pts(clock_time * gst::ClockTime::MSECOND);
-                            buffer_ref.copy_from_slice(0, frame.buf()).unwrap();
-                        };
-                        _ = source.push_buffer(buffer);
-                        i += 1;
-                    })
-                    .build(),
-            );
+            let audio_source = port.has_audio().then(|| {
+                bin.by_name_recurse_up("audio-source")
+                    .unwrap()
+                    .dynamic_cast::<gst_app::AppSrc>()
+                    .unwrap()
+            });
+
+            // Every client of a non-shared media gets its own stream of frames.
+            appsrc::feed(&source, audio_source.as_ref(), port.subscribe(), config).unwrap();
         }),
     );
 
@@ -140,6 +197,21 @@ impl Node for RtspSink {
         (*self).start();
         Ok(())
     }
+
+    fn stop_handle(&self) -> StopHandle {
+        let context = self.context.clone();
+        let main_loop = self.main_loop.clone();
+        // Quits in the context itself, so that it also works before the loop
+        // is running.
+        StopHandle::new(move || {
+            let main_loop = main_loop.clone();
+            context.invoke(move || main_loop.quit());
+        })
+    }
+
+    fn sink_port(&self) -> SinkPort {
+        self.port.clone()
+    }
 }
 
 struct Construct;
@@ -155,10 +227,10 @@ impl ConstructNode for Construct {
     fn construct(
         &self,
         inputs: Vec<NodeRef>,
-        _: Options,
+        options: Options,
         config: VideoConfig,
     ) -> Result<NodeRef, Error> {
-        RtspSink::new(inputs, config).map(NodeRef::new)
+        RtspSink::new(inputs, options, config).map(NodeRef::new)
     }
 
     fn is_sink(&self) -> bool {
@@ -1,2 +1,7 @@
+pub mod appsink;
 pub mod device;
+pub mod file;
+pub mod gst_audio;
+pub mod pcm;
 pub mod random_color;
+pub mod test_signal;
@@ -0,0 +1,158 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+use gst::prelude::*;
+
+use crate::util::{audio::AudioBuffer, Error, FrameId};
+
+// Caps for the final appsink of a source pipeline so that the samples can be
+// pushed into the buffer as they are.
+pub fn caps(channels: usize, sample_rate: usize) -> String {
+    format!("audio/x-raw,format=F32LE,layout=interleaved,channels={channels},rate={sample_rate}")
+}
+
+// In real-time mode, the samples are pushed into the buffer as soon as they
+// arrive. Synchronizing on the pipeline clock makes them arrive at real-time
+// pace.
+pub fn push_samples(sink: &gst_app::AppSink, buf: &AudioBuffer) {
+    sink.set_sync(true);
+    sink.set_callbacks(
+        gst_app::AppSinkCallbacks::builder()
+            .new_sample({
+                let buf = buf.clone();
+                move |sink| {
+                    let sample = sink.pull_sample().map_err(|_| gst::FlowError::Eos)?;
+                    buf.push(&to_samples(&sample).ok_or(gst::FlowError::Error)?);
+                    Ok(gst::FlowSuccess::Ok)
+                }
+            })
+            .build(),
+    );
+}
+
+// In offline mode, the samples are pulled on demand, exactly one frame per
+// `FrameId`.
+#[derive(Debug)]
+pub struct Offline {
+    sink: gst_app::AppSink,
+    pending: Vec<f32>,
+    last_id: FrameId,
+    eos: bool,
+}
+
+impl Offline {
+    pub fn new(sink: gst_app::AppSink) -> Self {
+        // The appsink only must not accumulate the whole stream in the
+        // meantime.
+        sink.set_sync(false);
+        sink.set_max_buffers(4);
+
+        Self {
+            sink,
+            pending: Vec::new(),
+            last_id: FrameId::default(),
+            eos: false,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.eos && self.pending.is_empty()
+    }
+
+    pub fn update(&mut self, id: FrameId, buf: &AudioBuffer) {
+        if self.last_id.update(id) {
+            self.advance(buf);
+        }
+    }
+
+    fn advance(&mut self, buf: &AudioBuffer) {
+        let frame_size = buf.frame_size() * buf.channels();
+
+        while !self.eos && self.pending.len() < frame_size {
+            match self.sink.pull_sample() {
+                Ok(sample) => self.pending.extend(to_samples(&sample).unwrap_or_default()),
+                // Either end of stream or an error, there is no more data in
+                // both cases.
+                Err(_) => self.eos = true,
+            }
+        }
+
+        if self.pending.len() < frame_size {
+            // Pad the last frame with silence.
+            self.pending.resize(frame_size, 0.0);
+        }
+
+        buf.push(&self.pending[..frame_size]);
+        self.pending.drain(..frame_size);
+    }
+}
+
+// Watches the bus until an error or end of stream, then sets `finished`. On end
+// of stream, `on_eos` can restart the pipeline and return true to keep
+// watching.
+pub fn watch_bus<F>(
+    pipeline: &gst::Pipeline,
+    finished: Arc<AtomicBool>,
+    mut on_eos: F,
+) -> Result<JoinHandle<()>, Error>
+where
+    F: FnMut(&gst::Pipeline) -> bool + Send + 'static,
+{
+    let bus = pipeline.bus().ok_or(Error::System)?;
+    let pipeline = pipeline.downgrade();
+
+    let watch = std::thread::spawn(move || {
+        for message in bus.iter_timed(gst::ClockTime::NONE) {
+            let pipeline = match pipeline.upgrade() {
+                Some(pipeline) => pipeline,
+                None => break,
+            };
+
+            match message.view() {
+                gst::MessageView::Eos(_) => {
+                    if !on_eos(&pipeline) {
+                        break;
+                    }
+                }
+                gst::MessageView::Error(_) => break,
+                _ => {}
+            }
+        }
+
+        finished.store(true, Ordering::Relaxed);
+    });
+
+    Ok(watch)
+}
+
+// Stops the pipeline and the thread watching its bus.
+pub fn shutdown(pipeline: &gst::Pipeline, watch: Option<JoinHandle<()>>) {
+    _ = pipeline.set_state(gst::State::Null);
+
+    if let Some(bus) = pipeline.bus() {
+        // Makes the watching thread stop waiting for messages.
+        bus.set_flushing(true);
+    }
+
+    if let Some(watch) = watch {
+        _ = watch.join();
+    }
+}
+
+fn to_samples(sample: &gst::Sample) -> Option<Vec<f32>> {
+    let buffer = sample.buffer()?;
+    let map = buffer.map_readable().ok()?;
+
+    let samples = map
+        .as_slice()
+        .chunks_exact(4)
+        .map(|sample| f32::from_le_bytes(sample.try_into().unwrap()))
+        .collect();
+
+    Some(samples)
+}
@@ -1,30 +1,83 @@
+use std::io;
+
+use crossbeam_channel::Receiver;
+
 use crate::{
-    options::Options,
+    options::{Options, Value},
     pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
-    util::{audio::AudioBuffer, inputs::validate_inputs, video::VideoConfig, Error, FrameId},
+    util::{
+        audio::{get_latency, AudioBuffer},
+        inputs::validate_inputs,
+        video::VideoConfig,
+        Error, FrameId,
+    },
 };
 
 #[derive(Debug)]
 pub struct DeviceSource {
     stream: streams::StreamHandle,
     buf: AudioBuffer,
+    last_id: FrameId,
 }
 
 impl DeviceSource {
-    pub fn new(inputs: Vec<NodeRef>, config: VideoConfig) -> Result<Self, Error> {
+    pub fn new(inputs: Vec<NodeRef>, options: Options, config: VideoConfig) -> Result<Self, Error> {
         validate_inputs(inputs, ())?;
 
-        let sample_rate = streams::get_sample_rate()?;
-        let buf = AudioBuffer::new(sample_rate.0 as usize, config.fps());
-
-        let stream = streams::build({
+        let channels = options
+            .get("channels")
+            .map(Channels::from_value)
+            .transpose()?
+            .unwrap_or(Channels::Mix);
+
+        let reconnect = options
+            .get("reconnect")
+            .unwrap_or(&true.into())
+            .as_bool()
+            .ok_or(Error::InvalidOptions)?;
+
+        let selector = streams::Selector {
+            host: get_name(&options, "host")?,
+            device: get_name(&options, "device")?,
+            sample_rate: get_positive(&options, "sample-rate")?,
+            buffer_size: get_positive(&options, "buffer-size")?,
+        };
+
+        let stream_config = streams::get_config(selector.clone())?;
+        let device_channels = stream_config.channels as usize;
+        let selected = channels.indices(device_channels)?;
+
+        let buf = AudioBuffer::with_channels(
+            stream_config.sample_rate.0 as usize,
+            config.fps(),
+            selected.len(),
+        )
+        .with_latency(get_latency(&options)?);
+
+        let stream = streams::build(selector, reconnect, {
             let buf = buf.clone();
+            let all = selected.iter().copied().eq(0..device_channels);
+            let mut samples = Vec::new();
+
             move |data| {
-                buf.push(data);
+                if all {
+                    buf.push(data);
+                } else {
+                    samples.clear();
+                    samples.extend(
+                        data.chunks_exact(device_channels)
+                            .flat_map(|frame| selected.iter().map(|channel| frame[*channel])),
+                    );
+                    buf.push(&samples);
+                }
             }
         })?;
 
-        Ok(Self { stream, buf })
+        Ok(Self {
+            stream,
+            buf,
+            last_id: FrameId::default(),
+        })
     }
 
     pub fn play(&self) -> Result<(), Error> {
@@ -36,6 +89,85 @@ impl DeviceSource {
     }
 }
 
+fn get_name(options: &Options, name: &str) -> Result<Option<String>, Error> {
+    options
+        .get(name)
+        .map(|value| {
+            value
+                .as_str()
+                .map(|value| value.to_string())
+                .ok_or(Error::InvalidOptions)
+        })
+        .transpose()
+}
+
+fn get_positive(options: &Options, name: &str) -> Result<Option<u32>, Error> {
+    options
+        .get(name)
+        .map(|value| {
+            value
+                .as_i32()
+                .filter(|value| *value > 0)
+                .map(|value| value as u32)
+                .ok_or(Error::InvalidOptions)
+        })
+        .transpose()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Channels {
+    // All channels, read as their mono mix.
+    Mix,
+    Left,
+    Right,
+    Select(Vec<usize>),
+}
+
+impl Channels {
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        // A string is a slice of itself, the named selections must be
+        // matched first.
+        match value.as_str() {
+            Some("mix") => return Ok(Channels::Mix),
+            Some("left") => return Ok(Channels::Left),
+            Some("right") => return Ok(Channels::Right),
+            Some(_) => return Err(Error::InvalidOptions),
+            None => {}
+        }
+
+        let channels = value
+            .as_slice()
+            .ok_or(Error::InvalidOptions)?
+            .iter()
+            .map(|channel| {
+                channel
+                    .as_i32()
+                    .filter(|channel| *channel >= 0)
+                    .map(|channel| channel as usize)
+                    .ok_or(Error::InvalidOptions)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Channels::Select(channels))
+    }
+
+    // Indices of the selected channels among the device channels.
+    fn indices(&self, device_channels: usize) -> Result<Vec<usize>, Error> {
+        let indices = match self {
+            Channels::Mix => (0..device_channels).collect(),
+            Channels::Left => vec![0],
+            Channels::Right => vec![1],
+            Channels::Select(indices) => indices.clone(),
+        };
+
+        if indices.is_empty() || indices.iter().any(|index| *index >= device_channels) {
+            return Err(Error::InvalidOptions);
+        }
+
+        Ok(indices)
+    }
+}
+
 impl Drop for DeviceSource {
     fn drop(&mut self) {
         _ = streams::pause(self.stream.id());
@@ -47,7 +179,23 @@ impl Node for DeviceSource {
         matches!(cap, Capability::ProvideAudioData)
     }
 
-    fn provide_audio_data(&mut self, _: FrameId) -> AudioBuffer {
+    fn is_finished(&self) -> bool {
+        self.stream.status().is_failed()
+    }
+
+    // The source itself only goes silent while the device is disconnected and
+    // finishes once the stream failed.
+    fn events(&self) -> Option<Receiver<String>> {
+        Some(self.stream.events().clone())
+    }
+
+    fn provide_audio_data(&mut self, id: FrameId) -> AudioBuffer {
+        if self.last_id.update(id) && self.stream.status().is_disconnected() {
+            // Keep the sinks streaming while the device is gone.
+            let frame_size = self.buf.frame_size() * self.buf.channels();
+            self.buf.push(&vec![0.0; frame_size]);
+        }
+
         self.buf.clone()
     }
 }
@@ -65,10 +213,10 @@ impl ConstructNode for Construct {
     fn construct(
         &self,
         inputs: Vec<NodeRef>,
-        _: Options,
+        options: Options,
         config: VideoConfig,
     ) -> Result<NodeRef, Error> {
-        DeviceSource::new(inputs, config).map(NodeRef::new)
+        DeviceSource::new(inputs, options, config).map(NodeRef::new)
     }
 }
 
@@ -76,6 +224,11 @@ pub fn register(factory: &mut NodeFactory) {
     factory.register(Construct);
 }
 
+// Prints available hosts and their input devices with supported configurations.
+pub fn list_devices<W: io::Write>(out: &mut W) -> io::Result<()> {
+    streams::list(out)
+}
+
 mod streams {
     // Implementors of `Node` trait must be `Send` and `Sync`, however streams
     // are not `Send` nor `Sync`. For details see
@@ -85,17 +238,33 @@ mod streams {
     // forever-running thread that manages all streams throughout the
     // application runtime.
 
-    use std::collections::HashMap;
+    use std::{
+        collections::HashMap,
+        fmt, io,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
 
     use cpal::{
         traits::{DeviceTrait, HostTrait, StreamTrait},
-        SampleRate, Stream,
+        BufferSize, Device, Host, Sample, SampleFormat, SampleRate, Stream, StreamConfig,
+        StreamError, SupportedBufferSize,
     };
-    use crossbeam_channel::Sender;
+    use crossbeam_channel::{Receiver, Sender};
     use once_cell::sync::Lazy;
 
     use crate::util::Error;
 
+    // How often disconnected streams are tried to be rebuilt.
+    const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+    // Number of events kept for a stream until they are taken, the newer ones
+    // are counted and only their number is reported.
+    const EVENTS_CAPACITY: usize = 64;
+
     static MANAGER_THREAD: Lazy<Sender<(Command, Sender<Output>)>> = Lazy::new(|| {
         let (command_sender, command_receiver) =
             crossbeam_channel::unbounded::<(Command, Sender<Output>)>();
@@ -104,50 +273,87 @@ mod streams {
             let mut streams = HashMap::new();
             let mut counter = 0;
 
-            while let Ok((command, output_sender)) = command_receiver.recv() {
-                let output = match command {
-                    Command::GetSampleRate => match find_sample_rate() {
-                        Some(sample_rate) => Output::SampleRate(sample_rate),
-                        None => Output::Error,
-                    },
-                    Command::Build(callback) => match build_stream(callback) {
-                        Some(stream) => {
-                            let handle = StreamHandle(counter);
-                            counter += 1;
-
-                            streams.insert(handle.id(), stream);
-                            Output::Stream(handle)
+            // Errors are reported from the audio threads.
+            let (error_sender, error_receiver) = crossbeam_channel::unbounded();
+            let ticker = crossbeam_channel::tick(RECONNECT_INTERVAL);
+
+            loop {
+                crossbeam_channel::select! {
+                    recv(command_receiver) -> message => {
+                        let (command, output_sender) = match message {
+                            Ok(message) => message,
+                            Err(_) => break,
+                        };
+
+                        let output = match command {
+                            Command::GetConfig(selector) => match find_config(&selector) {
+                                Some(config) => Output::Config(config),
+                                None => Output::Error,
+                            },
+                            Command::Build(selector, reconnect, callback) => {
+                                let id = StreamId(counter);
+                                let (event_sender, event_receiver) =
+                                    crossbeam_channel::bounded(EVENTS_CAPACITY);
+                                let mut managed = ManagedStream {
+                                    id,
+                                    selector,
+                                    config: None,
+                                    callback: Arc::new(Mutex::new(callback)),
+                                    stream: None,
+                                    playing: false,
+                                    reconnect,
+                                    status: Arc::new(Status::default()),
+                                    events: event_sender,
+                                    dropped: 0,
+                                };
+
+                                if managed.connect(&error_sender) {
+                                    counter += 1;
+
+                                    let handle = StreamHandle {
+                                        id: id.0,
+                                        status: managed.status.clone(),
+                                        events: event_receiver,
+                                    };
+
+                                    streams.insert(id, managed);
+                                    Output::Stream(handle)
+                                } else {
+                                    Output::Error
+                                }
+                            }
+                            Command::Play(stream_id) => match streams.get_mut(&stream_id) {
+                                Some(managed) => managed.set_playing(true),
+                                None => Output::Error,
+                            },
+                            Command::Pause(stream_id) => match streams.get_mut(&stream_id) {
+                                Some(managed) => managed.set_playing(false),
+                                None => Output::Error,
+                            },
+                            Command::Drop(stream_id) => match streams.remove(&stream_id) {
+                                Some(_) => Output::Success,
+                                None => Output::Error,
+                            },
+                        };
+
+                        if output_sender.send(output).is_err() {
+                            break;
                         }
-                        None => Output::Error,
-                    },
-                    Command::Play(stream_id) => {
-                        match streams
-                            .get(&stream_id)
-                            .expect("stream has being dropped")
-                            .play()
-                        {
-                            Ok(_) => Output::Success,
-                            Err(_) => Output::Error,
+                    }
+                    recv(error_receiver) -> message => {
+                        let (stream_id, error) = message.unwrap();
+
+                        if let Some(managed) = streams.get_mut(&stream_id) {
+                            managed.handle_error(error);
                         }
                     }
-                    Command::Pause(stream_id) => match streams
-                        .get(&stream_id)
-                        .expect("stream has being dropped")
-                        .pause()
-                    {
-                        Ok(_) => Output::Success,
-                        Err(_) => Output::Error,
-                    },
-                    Command::Drop(stream_id) => {
-                        streams
-                            .remove(&stream_id)
-                            .expect("stream has being dropped");
-                        Output::Success
+                    recv(ticker) -> _ => {
+                        for managed in streams.values_mut() {
+                            if managed.stream.is_none() && managed.reconnect {
+                                managed.connect(&error_sender);
+                            }
+                        }
                     }
-                };
-
-                if output_sender.send(output).is_err() {
-                    break;
                 }
             }
         });
@@ -157,15 +363,38 @@ mod streams {
 
     pub type DataCallback = Box<dyn FnMut(&[f32]) + Send + 'static>;
 
+    // Which device and stream configuration to use. Unspecified values fall
+    // back to the defaults of the host and device.
+    #[derive(Debug, Clone, Default)]
+    pub struct Selector {
+        pub host: Option<String>,
+        // Exact name or a substring matching a single device.
+        pub device: Option<String>,
+        pub sample_rate: Option<u32>,
+        pub buffer_size: Option<u32>,
+    }
+
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct StreamId(usize);
 
     #[derive(Debug)]
-    pub struct StreamHandle(usize);
+    pub struct StreamHandle {
+        id: usize,
+        status: Arc<Status>,
+        events: Receiver<String>,
+    }
 
     impl StreamHandle {
         pub fn id(&self) -> StreamId {
-            StreamId(self.0)
+            StreamId(self.id)
+        }
+
+        pub fn status(&self) -> &Status {
+            &self.status
+        }
+
+        pub fn events(&self) -> &Receiver<String> {
+            &self.events
         }
     }
 
@@ -175,43 +404,370 @@ mod streams {
         }
     }
 
+    #[derive(Debug, Default)]
+    pub struct Status {
+        disconnected: AtomicBool,
+        failed: AtomicBool,
+    }
+
+    impl Status {
+        // The device is gone, but the stream might be rebuilt once it
+        // returns.
+        pub fn is_disconnected(&self) -> bool {
+            self.disconnected.load(Ordering::Relaxed)
+        }
+
+        // The stream stopped for good.
+        pub fn is_failed(&self) -> bool {
+            self.failed.load(Ordering::Relaxed)
+        }
+    }
+
+    // What happened to a stream, forwarded from the manager thread.
+    #[derive(Debug)]
+    enum StreamEvent {
+        // Backend specific error, the stream keeps running.
+        Error(StreamError),
+        // The device is gone. The stream is rebuilt once it returns if
+        // reconnecting is enabled, otherwise it failed.
+        Disconnected(StreamError),
+        Reconnected,
+    }
+
+    impl fmt::Display for StreamEvent {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Error(error) => write!(f, "{}", error),
+                Self::Disconnected(error) => write!(f, "disconnected, {}", error),
+                Self::Reconnected => write!(f, "reconnected"),
+            }
+        }
+    }
+
+    struct ManagedStream {
+        id: StreamId,
+        selector: Selector,
+        // The configuration of the first stream, rebuilt streams must have the
+        // same one.
+        config: Option<StreamConfig>,
+        callback: Arc<Mutex<DataCallback>>,
+        stream: Option<Stream>,
+        playing: bool,
+        reconnect: bool,
+        status: Arc<Status>,
+        events: Sender<String>,
+        // Events not sent because nobody took the previous ones.
+        dropped: usize,
+    }
+
+    impl ManagedStream {
+        fn connect(&mut self, errors: &Sender<(StreamId, StreamError)>) -> bool {
+            let id = self.id;
+            let errors = errors.clone();
+            let on_error = move |error| {
+                _ = errors.send((id, error));
+            };
+
+            let (stream, config) =
+                match build_stream(&self.selector, self.callback.clone(), on_error) {
+                    Some(built) => built,
+                    None => return false,
+                };
+
+            match self.config.as_ref() {
+                Some(expected)
+                    if expected.channels != config.channels
+                        || expected.sample_rate != config.sample_rate =>
+                {
+                    // The buffers downstream can't change, wait for the right
+                    // device.
+                    return false;
+                }
+                Some(_) => {}
+                None => self.config = Some(config),
+            }
+
+            if self.playing && stream.play().is_err() {
+                return false;
+            }
+
+            self.stream = Some(stream);
+
+            if self.status.disconnected.swap(false, Ordering::Relaxed) {
+                self.report(StreamEvent::Reconnected);
+            }
+
+            true
+        }
+
+        fn handle_error(&mut self, error: StreamError) {
+            if !matches!(error, StreamError::DeviceNotAvailable) {
+                // Backend specific errors are not necessarily fatal, the
+                // stream keeps running.
+                self.report(StreamEvent::Error(error));
+                return;
+            }
+
+            if self.stream.take().is_none() {
+                // Already disconnected, the errors might come in bursts.
+                return;
+            }
+
+            self.status.disconnected.store(true, Ordering::Relaxed);
+
+            if !self.reconnect {
+                self.status.failed.store(true, Ordering::Relaxed);
+            }
+
+            self.report(StreamEvent::Disconnected(error));
+        }
+
+        fn report(&mut self, event: StreamEvent) {
+            if self.dropped > 0 {
+                let message = format!(
+                    "audio stream {}: {} events dropped",
+                    self.id.0, self.dropped
+                );
+                if self.events.try_send(message).is_err() {
+                    self.dropped += 1;
+                    return;
+                }
+                self.dropped = 0;
+            }
+
+            let message = format!("audio stream {}: {}", self.id.0, event);
+            if self.events.try_send(message).is_err() {
+                self.dropped += 1;
+            }
+        }
+
+        fn set_playing(&mut self, playing: bool) -> Output {
+            self.playing = playing;
+
+            // Disconnected stream is played or paused after being rebuilt.
+            let result = match self.stream.as_ref() {
+                Some(stream) if playing => stream.play(),
+                Some(stream) => stream.pause(),
+                None => Ok(()),
+            };
+
+            match result {
+                Ok(_) => Output::Success,
+                Err(_) => Output::Error,
+            }
+        }
+    }
+
     enum Command {
-        GetSampleRate,
-        Build(DataCallback),
+        GetConfig(Selector),
+        Build(Selector, bool, DataCallback),
         Play(StreamId),
         Pause(StreamId),
         Drop(StreamId),
     }
 
     enum Output {
-        SampleRate(SampleRate),
+        Config(StreamConfig),
         Stream(StreamHandle),
         Success,
         Error,
     }
 
-    fn find_sample_rate() -> Option<SampleRate> {
-        let host = cpal::default_host();
-        let device = host.default_input_device()?;
-        let config = device.default_input_config().ok()?;
+    fn find_host(name: Option<&str>) -> Option<Host> {
+        match name {
+            Some(name) => cpal::available_hosts()
+                .into_iter()
+                .find(|id| id.name().eq_ignore_ascii_case(name))
+                .and_then(|id| cpal::host_from_id(id).ok()),
+            None => Some(cpal::default_host()),
+        }
+    }
+
+    fn find_device(host: &Host, name: Option<&str>) -> Option<Device> {
+        let name = match name {
+            Some(name) => name,
+            None => return host.default_input_device(),
+        };
+
+        let devices = host
+            .input_devices()
+            .ok()?
+            .filter_map(|device| Some((device.name().ok()?, device)))
+            .collect::<Vec<_>>();
+
+        if let Some(index) = devices.iter().position(|(device, _)| device == name) {
+            return devices.into_iter().nth(index).map(|(_, device)| device);
+        }
+
+        // Ambiguous substring is refused so that the choice does not depend on
+        // the order in which the devices are enumerated.
+        let mut matching = devices
+            .into_iter()
+            .filter(|(device, _)| device.contains(name))
+            .map(|(_, device)| device);
+
+        match (matching.next(), matching.next()) {
+            (Some(device), None) => Some(device),
+            _ => None,
+        }
+    }
+
+    fn find_config(selector: &Selector) -> Option<StreamConfig> {
+        let host = find_host(selector.host.as_deref())?;
+        let device = find_device(&host, selector.device.as_deref())?;
+        select_config(&device, selector).map(|(config, _)| config)
+    }
+
+    fn select_config(device: &Device, selector: &Selector) -> Option<(StreamConfig, SampleFormat)> {
+        let default = device.default_input_config().ok()?;
+        let mut config = default.config();
+
+        if let Some(sample_rate) = selector.sample_rate {
+            let supported = device.supported_input_configs().ok()?.any(|range| {
+                range.channels() == config.channels
+                    && range.sample_format() == default.sample_format()
+                    && (range.min_sample_rate().0..=range.max_sample_rate().0)
+                        .contains(&sample_rate)
+            });
+
+            if !supported {
+                return None;
+            }
+
+            config.sample_rate = SampleRate(sample_rate);
+        }
+
+        if let Some(buffer_size) = selector.buffer_size {
+            if let SupportedBufferSize::Range { min, max } = default.buffer_size() {
+                if !(*min..=*max).contains(&buffer_size) {
+                    return None;
+                }
+            }
+
+            config.buffer_size = BufferSize::Fixed(buffer_size);
+        }
+
+        Some((config, default.sample_format()))
+    }
+
+    fn build_stream<E>(
+        selector: &Selector,
+        callback: Arc<Mutex<DataCallback>>,
+        on_error: E,
+    ) -> Option<(Stream, StreamConfig)>
+    where
+        E: FnMut(StreamError) + Send + 'static,
+    {
+        let host = find_host(selector.host.as_deref())?;
+        let device = find_device(&host, selector.device.as_deref())?;
+        let (config, sample_format) = select_config(&device, selector)?;
 
-        Some(config.sample_rate())
+        let stream = match sample_format {
+            SampleFormat::F32 => build_input_stream::<f32, _>(&device, &config, callback, on_error),
+            SampleFormat::I16 => build_input_stream::<i16, _>(&device, &config, callback, on_error),
+            SampleFormat::U16 => build_input_stream::<u16, _>(&device, &config, callback, on_error),
+        }?;
+
+        Some((stream, config))
     }
 
-    fn build_stream(mut callback: DataCallback) -> Option<Stream> {
-        let host = cpal::default_host();
-        let device = host.default_input_device()?;
-        let config = device.default_input_config().ok()?;
+    // The samples are converted to f32 before they are passed to the callback.
+    fn build_input_stream<T, E>(
+        device: &Device,
+        config: &StreamConfig,
+        callback: Arc<Mutex<DataCallback>>,
+        on_error: E,
+    ) -> Option<Stream>
+    where
+        T: Sample,
+        E: FnMut(StreamError) + Send + 'static,
+    {
+        let mut samples = Vec::new();
 
         device
             .build_input_stream(
-                &config.config(),
-                move |data: &[f32], _| callback(data),
-                |_| {},
+                config,
+                move |data: &[T], _| {
+                    samples.clear();
+                    samples.extend(data.iter().map(Sample::to_f32));
+
+                    let mut callback = callback.lock().unwrap();
+                    (*callback)(&samples);
+                },
+                on_error,
             )
             .ok()
     }
 
+    // Does not need the manager thread as no stream is created.
+    pub fn list<W: io::Write>(out: &mut W) -> io::Result<()> {
+        let default_host = cpal::default_host().id();
+
+        for host_id in cpal::available_hosts() {
+            let default = if host_id == default_host {
+                " (default)"
+            } else {
+                ""
+            };
+            writeln!(out, "{}{}", host_id.name(), default)?;
+
+            let host = match cpal::host_from_id(host_id) {
+                Ok(host) => host,
+                Err(_) => {
+                    writeln!(out, "  unavailable")?;
+                    continue;
+                }
+            };
+
+            let default_device = host
+                .default_input_device()
+                .and_then(|device| device.name().ok());
+
+            let devices = match host.input_devices() {
+                Ok(devices) => devices,
+                Err(_) => continue,
+            };
+
+            for device in devices {
+                let name = match device.name() {
+                    Ok(name) => name,
+                    Err(_) => continue,
+                };
+
+                let default = if default_device.as_ref() == Some(&name) {
+                    " (default)"
+                } else {
+                    ""
+                };
+                writeln!(out, "  \"{}\"{}", name, default)?;
+
+                let configs = match device.supported_input_configs() {
+                    Ok(configs) => configs,
+                    Err(_) => continue,
+                };
+
+                for config in configs {
+                    let buffer_size = match config.buffer_size() {
+                        SupportedBufferSize::Range { min, max } => format!("{min}-{max}"),
+                        SupportedBufferSize::Unknown => String::from("unknown"),
+                    };
+
+                    writeln!(
+                        out,
+                        "    channels: {}, sample rate: {}-{} Hz, format: {:?}, buffer size: {}",
+                        config.channels(),
+                        config.min_sample_rate().0,
+                        config.max_sample_rate().0,
+                        config.sample_format(),
+                        buffer_size,
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn send_command(command: Command) -> Result<Output, Error> {
         let (sender, receiver) = crossbeam_channel::bounded(1);
         if MANAGER_THREAD.send((command, sender)).is_err() {
@@ -221,18 +777,20 @@ mod streams {
         receiver.recv().map_err(|_| Error::System)
     }
 
-    pub fn get_sample_rate() -> Result<SampleRate, Error> {
-        match send_command(Command::GetSampleRate)? {
-            Output::SampleRate(sample_rate) => Ok(sample_rate),
+    pub fn get_config(selector: Selector) -> Result<StreamConfig, Error> {
+        match send_command(Command::GetConfig(selector))? {
+            Output::Config(config) => Ok(config),
             _ => Err(Error::System),
         }
     }
 
-    pub fn build<F>(callback: F) -> Result<StreamHandle, Error>
+    // If `reconnect` is set, the stream is rebuilt when the device returns after
+    // being disconnected.
+    pub fn build<F>(selector: Selector, reconnect: bool, callback: F) -> Result<StreamHandle, Error>
     where
         F: FnMut(&[f32]) + Send + 'static,
     {
-        match send_command(Command::Build(Box::new(callback)))? {
+        match send_command(Command::Build(selector, reconnect, Box::new(callback)))? {
             Output::Stream(stream) => Ok(stream),
             _ => Err(Error::System),
         }
@@ -252,3 +810,42 @@ mod streams {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(value: Value) -> Result<Channels, Error> {
+        Channels::from_value(&value)
+    }
+
+    #[test]
+    fn named_channels() {
+        assert_eq!(channels("mix".to_string().into()).unwrap(), Channels::Mix);
+        assert_eq!(channels("left".to_string().into()).unwrap(), Channels::Left);
+        assert_eq!(
+            channels("right".to_string().into()).unwrap(),
+            Channels::Right
+        );
+        assert!(channels("center".to_string().into()).is_err());
+    }
+
+    #[test]
+    fn selected_channels() {
+        assert_eq!(channels(1.into()).unwrap(), Channels::Select(vec![1]));
+        assert_eq!(
+            channels(Value::Sequence(vec![2.into(), 0.into()])).unwrap(),
+            Channels::Select(vec![2, 0])
+        );
+        assert!(channels((-1).into()).is_err());
+        assert!(channels(true.into()).is_err());
+    }
+
+    #[test]
+    fn indices() {
+        assert_eq!(Channels::Mix.indices(2).unwrap(), [0, 1]);
+        assert_eq!(Channels::Right.indices(2).unwrap(), [1]);
+        assert!(Channels::Right.indices(1).is_err());
+        assert!(Channels::Select(vec![2]).indices(2).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+use gst::prelude::*;
+
+use crate::{
+    options::Options,
+    pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
+    util::{
+        audio::{get_latency, AudioBuffer},
+        inputs::validate_inputs,
+        video::VideoConfig,
+        Error, FrameId,
+    },
+};
+
+use super::appsink::{self, Offline};
+
+const SAMPLE_RATE: usize = 44100;
+
+#[derive(Debug)]
+pub struct FileSource {
+    pipeline: gst::Pipeline,
+    watch: Option<JoinHandle<()>>,
+    buf: AudioBuffer,
+    finished: Arc<AtomicBool>,
+    offline: Option<Offline>,
+}
+
+impl FileSource {
+    pub fn new(inputs: Vec<NodeRef>, options: Options, config: VideoConfig) -> Result<Self, Error> {
+        validate_inputs(inputs, ())?;
+
+        let path = options
+            .get("path")
+            .ok_or(Error::InvalidOptions)?
+            .as_str()
+            .ok_or(Error::InvalidOptions)?;
+
+        let looping = options
+            .get("loop")
+            .unwrap_or(&false.into())
+            .as_bool()
+            .ok_or(Error::InvalidOptions)?;
+
+        let start_offset = options
+            .get("start-offset-ms")
+            .unwrap_or(&0.0.into())
+            .as_f32()
+            .filter(|offset| *offset >= 0.0)
+            .ok_or(Error::InvalidOptions)?;
+
+        let playback_rate = options
+            .get("playback-rate")
+            .unwrap_or(&1.0.into())
+            .as_f32()
+            .filter(|rate| *rate > 0.0)
+            .ok_or(Error::InvalidOptions)?;
+
+        if looping && config.is_offline() {
+            // The rendering would never end.
+            return Err(Error::InvalidOptions);
+        }
+
+        gst::init().map_err(|_| Error::System)?;
+
+        let path = std::fs::canonicalize(path).map_err(|_| Error::InvalidOptions)?;
+        let uri = glib::filename_to_uri(path, None).map_err(|_| Error::InvalidOptions)?;
+
+        // The decoded stream is downmixed and resampled so that the buffer
+        // always gets mono data with a known sample rate. Scaletempo keeps the
+        // pitch when the playback rate is changed.
+        let pipeline = gst::parse_launch(&format!(
+            "uridecodebin uri={uri} ! audioconvert ! scaletempo ! audioconvert ! audioresample \
+             ! appsink name=sink caps={}",
+            appsink::caps(1, SAMPLE_RATE)
+        ))
+        .map_err(|_| Error::System)?
+        .dynamic_cast::<gst::Pipeline>()
+        .map_err(|_| Error::System)?;
+
+        let sink = pipeline
+            .by_name("sink")
+            .ok_or(Error::System)?
+            .dynamic_cast::<gst_app::AppSink>()
+            .map_err(|_| Error::System)?;
+
+        let buf = AudioBuffer::new(SAMPLE_RATE, config.fps()).with_latency(get_latency(&options)?);
+
+        let offline = if config.is_offline() {
+            Some(Offline::new(sink))
+        } else {
+            appsink::push_samples(&sink, &buf);
+            None
+        };
+
+        let seek = Seek {
+            rate: playback_rate as f64,
+            position: gst::ClockTime::from_mseconds(start_offset as u64),
+        };
+
+        pipeline
+            .set_state(gst::State::Paused)
+            .map_err(|_| Error::System)?;
+
+        // Seeking is possible only after the pipeline is prerolled.
+        pipeline
+            .state(gst::ClockTime::NONE)
+            .0
+            .map_err(|_| Error::System)?;
+
+        seek.perform(&pipeline)?;
+
+        pipeline
+            .set_state(gst::State::Playing)
+            .map_err(|_| Error::System)?;
+
+        let finished = Arc::new(AtomicBool::new(false));
+        let watch = Some(appsink::watch_bus(
+            &pipeline,
+            finished.clone(),
+            move |pipeline| looping && seek.perform(pipeline).is_ok(),
+        )?);
+
+        Ok(Self {
+            pipeline,
+            watch,
+            buf,
+            finished,
+            offline,
+        })
+    }
+}
+
+impl Drop for FileSource {
+    fn drop(&mut self) {
+        appsink::shutdown(&self.pipeline, self.watch.take());
+    }
+}
+
+impl Node for FileSource {
+    fn has_capability(&self, cap: Capability) -> bool {
+        matches!(cap, Capability::ProvideAudioData)
+    }
+
+    fn is_finished(&self) -> bool {
+        match self.offline.as_ref() {
+            Some(offline) => offline.is_finished(),
+            None => self.finished.load(Ordering::Relaxed),
+        }
+    }
+
+    fn provide_audio_data(&mut self, id: FrameId) -> AudioBuffer {
+        if let Some(offline) = self.offline.as_mut() {
+            offline.update(id, &self.buf);
+        }
+
+        self.buf.clone()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Seek {
+    rate: f64,
+    position: gst::ClockTime,
+}
+
+impl Seek {
+    fn perform(&self, pipeline: &gst::Pipeline) -> Result<(), Error> {
+        pipeline
+            .seek(
+                self.rate,
+                gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+                gst::SeekType::Set,
+                Some(self.position),
+                gst::SeekType::None,
+                gst::ClockTime::NONE,
+            )
+            .map_err(|_| Error::System)
+    }
+}
+
+struct Construct;
+
+impl ConstructNode for Construct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "file"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        FileSource::new(inputs, options, config).map(NodeRef::new)
+    }
+}
+
+pub fn register(factory: &mut NodeFactory) {
+    factory.register(Construct);
+}
@@ -0,0 +1,155 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+use gst::prelude::*;
+
+use crate::{
+    options::Options,
+    pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
+    util::{
+        audio::{get_latency, AudioBuffer},
+        inputs::validate_inputs,
+        video::VideoConfig,
+        Error, FrameId,
+    },
+};
+
+use super::appsink::{self, Offline};
+
+const DEFAULT_SAMPLE_RATE: i32 = 44100;
+
+// Audio from an arbitrary GStreamer pipeline given by a launch string, e.g.,
+// `pulsesrc device=... ! audioconvert`.
+#[derive(Debug)]
+pub struct GstAudioSource {
+    pipeline: gst::Pipeline,
+    watch: Option<JoinHandle<()>>,
+    buf: AudioBuffer,
+    finished: Arc<AtomicBool>,
+    offline: Option<Offline>,
+}
+
+impl GstAudioSource {
+    pub fn new(inputs: Vec<NodeRef>, options: Options, config: VideoConfig) -> Result<Self, Error> {
+        validate_inputs(inputs, ())?;
+
+        let launch = options
+            .get("launch")
+            .ok_or(Error::InvalidOptions)?
+            .as_str()
+            .ok_or(Error::InvalidOptions)?;
+
+        let channels = options
+            .get("channels")
+            .unwrap_or(&1.into())
+            .as_i32()
+            .filter(|channels| (1..=2).contains(channels))
+            .ok_or(Error::InvalidOptions)? as usize;
+
+        let sample_rate = options
+            .get("sample-rate")
+            .unwrap_or(&DEFAULT_SAMPLE_RATE.into())
+            .as_i32()
+            .filter(|rate| *rate > 0)
+            .ok_or(Error::InvalidOptions)? as usize;
+
+        gst::init().map_err(|_| Error::System)?;
+
+        // Whatever the launch string produces is converted to the format the
+        // buffer expects.
+        let pipeline = gst::parse_launch(&format!(
+            "{launch} ! audioconvert ! audioresample ! appsink name=picasound-sink caps={}",
+            appsink::caps(channels, sample_rate)
+        ))
+        .map_err(|_| Error::InvalidOptions)?
+        .dynamic_cast::<gst::Pipeline>()
+        .map_err(|_| Error::InvalidOptions)?;
+
+        let sink = pipeline
+            .by_name("picasound-sink")
+            .ok_or(Error::System)?
+            .dynamic_cast::<gst_app::AppSink>()
+            .map_err(|_| Error::System)?;
+
+        let buf = AudioBuffer::with_channels(sample_rate, config.fps(), channels)
+            .with_latency(get_latency(&options)?);
+
+        let offline = if config.is_offline() {
+            Some(Offline::new(sink))
+        } else {
+            appsink::push_samples(&sink, &buf);
+            None
+        };
+
+        pipeline
+            .set_state(gst::State::Playing)
+            .map_err(|_| Error::System)?;
+
+        let finished = Arc::new(AtomicBool::new(false));
+        let watch = Some(appsink::watch_bus(&pipeline, finished.clone(), |_| false)?);
+
+        Ok(Self {
+            pipeline,
+            watch,
+            buf,
+            finished,
+            offline,
+        })
+    }
+}
+
+impl Drop for GstAudioSource {
+    fn drop(&mut self) {
+        appsink::shutdown(&self.pipeline, self.watch.take());
+    }
+}
+
+impl Node for GstAudioSource {
+    fn has_capability(&self, cap: Capability) -> bool {
+        matches!(cap, Capability::ProvideAudioData)
+    }
+
+    fn is_finished(&self) -> bool {
+        match self.offline.as_ref() {
+            Some(offline) => offline.is_finished(),
+            None => self.finished.load(Ordering::Relaxed),
+        }
+    }
+
+    fn provide_audio_data(&mut self, id: FrameId) -> AudioBuffer {
+        if let Some(offline) = self.offline.as_mut() {
+            offline.update(id, &self.buf);
+        }
+
+        self.buf.clone()
+    }
+}
+
+struct Construct;
+
+impl ConstructNode for Construct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "gst-audio"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        GstAudioSource::new(inputs, options, config).map(NodeRef::new)
+    }
+}
+
+pub fn register(factory: &mut NodeFactory) {
+    factory.register(Construct);
+}
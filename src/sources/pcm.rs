@@ -0,0 +1,328 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::PathBuf,
+};
+
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+
+use crate::{
+    options::{Options, Value},
+    pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
+    util::{
+        audio::{get_latency, AudioBuffer},
+        inputs::validate_inputs,
+        video::VideoConfig,
+        Error, FrameId,
+    },
+};
+
+const DEFAULT_SAMPLE_RATE: i32 = 44100;
+
+// Number of chunks read ahead before the reader blocks.
+const QUEUE_CAPACITY: usize = 64;
+
+// Number of consecutive frames without data after which the source is
+// considered to underrun. Tolerates jitter of the writer.
+const UNDERRUN_FRAMES: usize = 2;
+
+// Raw interleaved samples read from stdin or a named pipe, e.g., produced by
+// `ffmpeg -i ... -f f32le -ac 2 -ar 44100 -`.
+#[derive(Debug)]
+pub struct PcmSource {
+    buf: AudioBuffer,
+    receiver: Receiver<Vec<f32>>,
+    pending: Vec<f32>,
+    last_id: FrameId,
+    offline: bool,
+    // Samples to be dropped from the incoming data to make up for the silence
+    // inserted during an underrun.
+    debt: usize,
+    missed: usize,
+    eof: bool,
+}
+
+impl PcmSource {
+    pub fn new(
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+        input: Input,
+    ) -> Result<Self, Error> {
+        validate_inputs(inputs, ())?;
+
+        let format = options
+            .get("format")
+            .map(Format::from_value)
+            .transpose()?
+            .unwrap_or(Format::F32Le);
+
+        let channels = options
+            .get("channels")
+            .unwrap_or(&1.into())
+            .as_i32()
+            .filter(|channels| (1..=32).contains(channels))
+            .ok_or(Error::InvalidOptions)? as usize;
+
+        let sample_rate = options
+            .get("sample-rate")
+            .unwrap_or(&DEFAULT_SAMPLE_RATE.into())
+            .as_i32()
+            .filter(|rate| *rate > 0)
+            .ok_or(Error::InvalidOptions)? as usize;
+
+        let buf = AudioBuffer::with_channels(sample_rate, config.fps(), channels)
+            .with_latency(get_latency(&options)?);
+        let reader = input.open().map_err(|_| Error::System)?;
+        let (sender, receiver) = crossbeam_channel::bounded(QUEUE_CAPACITY);
+
+        // The reader is detached, it might be blocked in reading forever. It
+        // stops on the next read after the source is dropped.
+        std::thread::spawn({
+            let frame_size = buf.frame_size();
+            move || read(reader, format, channels, frame_size, sender)
+        });
+
+        Ok(Self {
+            buf,
+            receiver,
+            pending: Vec::new(),
+            last_id: FrameId::default(),
+            offline: config.is_offline(),
+            debt: 0,
+            missed: 0,
+            eof: false,
+        })
+    }
+
+    fn advance_realtime(&mut self) {
+        let mut received = false;
+
+        loop {
+            match self.receiver.try_recv() {
+                Ok(samples) => {
+                    received = true;
+
+                    let skip = self.debt.min(samples.len());
+                    self.debt -= skip;
+                    self.push(&samples[skip..]);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.eof = true;
+                    break;
+                }
+            }
+        }
+
+        if received {
+            self.missed = 0;
+        } else {
+            self.missed += 1;
+        }
+
+        if self.missed >= UNDERRUN_FRAMES && !self.eof {
+            // Rather than repeating stale data, the source goes silent until
+            // the data arrive again.
+            let frame_size = self.buf.frame_size() * self.buf.channels();
+            self.push(&vec![0.0; frame_size]);
+            self.debt += frame_size;
+        }
+    }
+
+    fn advance_offline(&mut self) {
+        let frame_size = self.buf.frame_size() * self.buf.channels();
+
+        while !self.eof && self.pending.len() < frame_size {
+            match self.receiver.recv() {
+                Ok(samples) => self.pending.extend(samples),
+                Err(_) => self.eof = true,
+            }
+        }
+
+        if self.pending.len() < frame_size {
+            // Pad the last frame with silence.
+            self.pending.resize(frame_size, 0.0);
+        }
+
+        self.buf.push(&self.pending[..frame_size]);
+        self.pending.drain(..frame_size);
+    }
+
+    // The buffer does not accept arbitrarily large chunks.
+    fn push(&self, samples: &[f32]) {
+        let frame_size = self.buf.frame_size() * self.buf.channels();
+
+        for chunk in samples.chunks(frame_size) {
+            self.buf.push(chunk);
+        }
+    }
+}
+
+impl Node for PcmSource {
+    fn has_capability(&self, cap: Capability) -> bool {
+        matches!(cap, Capability::ProvideAudioData)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.eof && self.pending.is_empty()
+    }
+
+    fn provide_audio_data(&mut self, id: FrameId) -> AudioBuffer {
+        if self.last_id.update(id) {
+            if self.offline {
+                self.advance_offline();
+            } else {
+                self.advance_realtime();
+            }
+        }
+
+        self.buf.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Input {
+    Stdin,
+    Pipe(PathBuf),
+}
+
+impl Input {
+    // Opening a named pipe blocks until the writer opens it too, so does
+    // constructing the source.
+    fn open(self) -> io::Result<Box<dyn Read + Send>> {
+        match self {
+            Input::Stdin => Ok(Box::new(io::stdin())),
+            Input::Pipe(path) => Ok(Box::new(File::open(path)?)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    F32Le,
+    S16Le,
+}
+
+impl Format {
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        match value.as_str() {
+            Some("f32le") => Ok(Format::F32Le),
+            Some("s16le") => Ok(Format::S16Le),
+            _ => Err(Error::InvalidOptions),
+        }
+    }
+
+    fn sample_size(&self) -> usize {
+        match self {
+            Format::F32Le => 4,
+            Format::S16Le => 2,
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> f32 {
+        match self {
+            Format::F32Le => f32::from_le_bytes(bytes.try_into().unwrap()),
+            Format::S16Le => i16::from_le_bytes(bytes.try_into().unwrap()) as f32 / 32768.0,
+        }
+    }
+}
+
+// Reads the input in chunks of whole frames until the end of the input or until
+// the receiver is dropped.
+fn read(
+    mut reader: Box<dyn Read + Send>,
+    format: Format,
+    channels: usize,
+    frame_size: usize,
+    sender: Sender<Vec<f32>>,
+) {
+    // Size of the samples of all channels at one point in time.
+    let sample_frame = channels * format.sample_size();
+
+    let mut bytes = vec![0; frame_size * sample_frame];
+    let mut filled = 0;
+
+    loop {
+        let eof = match reader.read(&mut bytes[filled..]) {
+            Ok(0) => true,
+            Ok(n) => {
+                filled += n;
+                false
+            }
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => true,
+        };
+
+        // At the end, the samples read so far are sent too, without an
+        // incomplete one.
+        if filled == bytes.len() || eof {
+            let samples = bytes[..(filled - filled % sample_frame)]
+                .chunks_exact(format.sample_size())
+                .map(|sample| format.decode(sample))
+                .collect::<Vec<_>>();
+
+            if !samples.is_empty() && sender.send(samples).is_err() {
+                break;
+            }
+
+            filled = 0;
+        }
+
+        if eof {
+            break;
+        }
+    }
+}
+
+struct StdinConstruct;
+
+impl ConstructNode for StdinConstruct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "pcm-stdin"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        PcmSource::new(inputs, options, config, Input::Stdin).map(NodeRef::new)
+    }
+}
+
+struct PipeConstruct;
+
+impl ConstructNode for PipeConstruct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "pcm-pipe"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        let path = options
+            .get("path")
+            .ok_or(Error::InvalidOptions)?
+            .as_str()
+            .ok_or(Error::InvalidOptions)?
+            .into();
+
+        PcmSource::new(inputs, options, config, Input::Pipe(path)).map(NodeRef::new)
+    }
+}
+
+pub fn register(factory: &mut NodeFactory) {
+    factory.register(StdinConstruct);
+    factory.register(PipeConstruct);
+}
@@ -92,7 +92,7 @@ impl Node for RandomColor {
             if self.cells.len() == 1 {
                 let (red, blue, green) = rng.gen();
 
-                frame.apply(|_, pixel| {
+                frame.par_apply(|_, pixel| {
                     pixel.set_red(red);
                     pixel.set_green(green);
                     pixel.set_blue(blue);
@@ -101,7 +101,7 @@ impl Node for RandomColor {
                 let cells = &self.cells;
                 let colors = cells.iter().map(|_| rng.gen()).collect::<Vec<_>>();
 
-                frame.apply(|(x, y), pixel| {
+                frame.par_apply(|(x, y), pixel| {
                     let cell_index = cells
                         .iter()
                         .enumerate()
@@ -0,0 +1,433 @@
+use std::{
+    f32::consts::PI,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    options::{Options, Value},
+    pipeline::{Capability, ConstructNode, Node, NodeFactory, NodeRef},
+    util::{
+        audio::{get_latency, AudioBuffer},
+        inputs::validate_inputs,
+        video::VideoConfig,
+        Error, FrameId,
+    },
+};
+
+const DEFAULT_SAMPLE_RATE: i32 = 44100;
+const DEFAULT_FREQUENCY: f32 = 440.0;
+const DEFAULT_SWEEP_RANGE: (f32, f32) = (20.0, 20000.0);
+
+// How often the samples are generated in real-time mode.
+const INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug)]
+pub struct TestSignal {
+    buf: AudioBuffer,
+    thread: Option<JoinHandle<()>>,
+    running: Arc<AtomicBool>,
+    offline: Option<Offline>,
+}
+
+impl TestSignal {
+    pub fn new(inputs: Vec<NodeRef>, options: Options, config: VideoConfig) -> Result<Self, Error> {
+        validate_inputs(inputs, ())?;
+
+        let generator = Generator::from_options(&options)?;
+        let buf = AudioBuffer::new(generator.sample_rate, config.fps())
+            .with_latency(get_latency(&options)?);
+        let running = Arc::new(AtomicBool::new(true));
+
+        if config.is_offline() {
+            let offline = Some(Offline {
+                generator,
+                samples: vec![0.0; buf.frame_size()],
+                last_id: FrameId::default(),
+            });
+
+            Ok(Self {
+                buf,
+                thread: None,
+                running,
+                offline,
+            })
+        } else {
+            let thread = std::thread::spawn({
+                let buf = buf.clone();
+                let running = running.clone();
+                move || generate(generator, buf, running)
+            });
+
+            Ok(Self {
+                buf,
+                thread: Some(thread),
+                running,
+                offline: None,
+            })
+        }
+    }
+}
+
+impl Drop for TestSignal {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            _ = thread.join();
+        }
+    }
+}
+
+impl Node for TestSignal {
+    fn has_capability(&self, cap: Capability) -> bool {
+        matches!(cap, Capability::ProvideAudioData)
+    }
+
+    fn provide_audio_data(&mut self, id: FrameId) -> AudioBuffer {
+        if let Some(offline) = self.offline.as_mut() {
+            if offline.last_id.update(id) {
+                offline.generator.fill(&mut offline.samples);
+                self.buf.push(&offline.samples);
+            }
+        }
+
+        self.buf.clone()
+    }
+}
+
+#[derive(Debug)]
+struct Offline {
+    generator: Generator,
+    samples: Vec<f32>,
+    last_id: FrameId,
+}
+
+// Generates the samples according to the wall clock.
+fn generate(mut generator: Generator, buf: AudioBuffer, running: Arc<AtomicBool>) {
+    let start = Instant::now();
+    let mut generated = 0u64;
+    let mut samples = Vec::new();
+
+    while running.load(Ordering::Relaxed) {
+        let due = (start.elapsed().as_secs_f64() * generator.sample_rate as f64) as u64;
+        // The buffer does not accept arbitrarily large chunks.
+        let n = ((due - generated) as usize).min(10 * buf.frame_size());
+
+        samples.resize(n, 0.0);
+        generator.fill(&mut samples);
+        buf.push(&samples);
+        generated += n as u64;
+
+        std::thread::sleep(INTERVAL);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Waveform {
+    Sine,
+    Square,
+    Saw,
+    White,
+    Pink,
+    Sweep,
+    Clicks,
+}
+
+impl Waveform {
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        match value.as_str() {
+            Some("sine") => Ok(Waveform::Sine),
+            Some("square") => Ok(Waveform::Square),
+            Some("saw") => Ok(Waveform::Saw),
+            Some("white") => Ok(Waveform::White),
+            Some("pink") => Ok(Waveform::Pink),
+            Some("sweep") => Ok(Waveform::Sweep),
+            Some("clicks") => Ok(Waveform::Clicks),
+            _ => Err(Error::InvalidOptions),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Generator {
+    waveform: Waveform,
+    // Start and end frequency of the sweep, both the same otherwise.
+    frequency: (f32, f32),
+    amplitude: f32,
+    sample_rate: usize,
+    // In samples.
+    sweep_length: u64,
+    beat_length: u64,
+    click_length: u64,
+    position: u64,
+    phase: f32,
+    rng: StdRng,
+    pink: [f32; 7],
+}
+
+impl Generator {
+    fn from_options(options: &Options) -> Result<Self, Error> {
+        let waveform = options
+            .get("waveform")
+            .map(Waveform::from_value)
+            .transpose()?
+            .unwrap_or(Waveform::Sine);
+
+        let sample_rate = options
+            .get("sample-rate")
+            .unwrap_or(&DEFAULT_SAMPLE_RATE.into())
+            .as_i32()
+            .filter(|rate| (1000..=192000).contains(rate))
+            .ok_or(Error::InvalidOptions)? as usize;
+
+        let frequency = match (waveform, options.get("frequency")) {
+            (Waveform::Sweep, Some(value)) => match value.as_slice() {
+                Some([start, end]) => (
+                    start.as_f32().ok_or(Error::InvalidOptions)?,
+                    end.as_f32().ok_or(Error::InvalidOptions)?,
+                ),
+                _ => return Err(Error::InvalidOptions),
+            },
+            (Waveform::Sweep, None) => DEFAULT_SWEEP_RANGE,
+            (_, value) => {
+                let frequency = value
+                    .unwrap_or(&DEFAULT_FREQUENCY.into())
+                    .as_f32()
+                    .ok_or(Error::InvalidOptions)?;
+                (frequency, frequency)
+            }
+        };
+
+        let nyquist = sample_rate as f32 / 2.0;
+        if [frequency.0, frequency.1]
+            .iter()
+            .any(|frequency| *frequency <= 0.0 || *frequency > nyquist)
+        {
+            return Err(Error::InvalidOptions);
+        }
+
+        let amplitude = options
+            .get("amplitude")
+            .unwrap_or(&0.5.into())
+            .as_f32()
+            .filter(|amplitude| (0.0..=1.0).contains(amplitude))
+            .ok_or(Error::InvalidOptions)?;
+
+        let bpm = options
+            .get("bpm")
+            .unwrap_or(&120.0.into())
+            .as_f32()
+            .filter(|bpm| *bpm > 0.0)
+            .ok_or(Error::InvalidOptions)?;
+
+        // In seconds.
+        let sweep_duration = options
+            .get("sweep-duration")
+            .unwrap_or(&10.0.into())
+            .as_f32()
+            .filter(|duration| *duration > 0.0)
+            .ok_or(Error::InvalidOptions)?;
+
+        let seed = options
+            .get("seed")
+            .unwrap_or(&0.into())
+            .as_i32()
+            .ok_or(Error::InvalidOptions)?;
+
+        Ok(Self::new(
+            waveform,
+            frequency,
+            amplitude,
+            sample_rate,
+            bpm,
+            sweep_duration,
+            seed as u64,
+        ))
+    }
+
+    fn new(
+        waveform: Waveform,
+        frequency: (f32, f32),
+        amplitude: f32,
+        sample_rate: usize,
+        bpm: f32,
+        sweep_duration: f32,
+        seed: u64,
+    ) -> Self {
+        let sweep_length = ((sweep_duration * sample_rate as f32) as u64).max(1);
+        let beat_length = ((60.0 / bpm * sample_rate as f32) as u64).max(1);
+        // Clicks last 10 ms, but at most half of the beat.
+        let click_length = (sample_rate as u64 / 100).min(beat_length / 2).max(1);
+
+        Self {
+            waveform,
+            frequency,
+            amplitude,
+            sample_rate,
+            sweep_length,
+            beat_length,
+            click_length,
+            position: 0,
+            phase: 0.0,
+            rng: StdRng::seed_from_u64(seed),
+            pink: [0.0; 7],
+        }
+    }
+
+    fn fill(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            *sample = self.amplitude * self.next();
+        }
+    }
+
+    fn next(&mut self) -> f32 {
+        let phase = self.phase;
+        let frequency = self.current_frequency();
+
+        self.phase = (self.phase + frequency / self.sample_rate as f32).fract();
+
+        let value = match self.waveform {
+            Waveform::Sine | Waveform::Sweep => (2.0 * PI * phase).sin(),
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Saw => 2.0 * phase - 1.0,
+            Waveform::White => self.rng.gen_range(-1.0..=1.0),
+            Waveform::Pink => self.pink(),
+            Waveform::Clicks => {
+                let offset = self.position % self.beat_length;
+
+                if offset < self.click_length {
+                    // Decaying tone starting at the beat.
+                    let decay = 1.0 - offset as f32 / self.click_length as f32;
+                    let t = offset as f32 / self.sample_rate as f32;
+                    decay * (2.0 * PI * frequency * t).sin()
+                } else {
+                    0.0
+                }
+            }
+        };
+
+        self.position += 1;
+        value
+    }
+
+    fn current_frequency(&self) -> f32 {
+        match self.waveform {
+            Waveform::Sweep => {
+                // Exponential sweep, so that each octave takes the same time.
+                let t = (self.position % self.sweep_length) as f32 / self.sweep_length as f32;
+                self.frequency.0 * (self.frequency.1 / self.frequency.0).powf(t)
+            }
+            _ => self.frequency.0,
+        }
+    }
+
+    // Paul Kellet's filter applied to white noise.
+    // https://www.firstpr.com.au/dsp/pink-noise/
+    fn pink(&mut self) -> f32 {
+        let white = self.rng.gen_range(-1.0..=1.0);
+        let b = &mut self.pink;
+
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.1538520;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+
+        // The filter has a gain of roughly 9.
+        (pink * 0.11).clamp(-1.0, 1.0)
+    }
+}
+
+struct Construct;
+
+impl ConstructNode for Construct {
+    fn node_type() -> &'static str
+    where
+        Self: Sized,
+    {
+        "test-signal"
+    }
+
+    fn construct(
+        &self,
+        inputs: Vec<NodeRef>,
+        options: Options,
+        config: VideoConfig,
+    ) -> Result<NodeRef, Error> {
+        TestSignal::new(inputs, options, config).map(NodeRef::new)
+    }
+}
+
+pub fn register(factory: &mut NodeFactory) {
+    factory.register(Construct);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(waveform: Waveform, frequency: f32, n: usize) -> Vec<f32> {
+        let mut generator =
+            Generator::new(waveform, (frequency, frequency), 1.0, 1000, 60.0, 1.0, 0);
+        let mut samples = vec![0.0; n];
+        generator.fill(&mut samples);
+        samples
+    }
+
+    #[test]
+    fn sine() {
+        let samples = generate(Waveform::Sine, 10.0, 200);
+
+        assert!(samples[0].abs() < 1e-6);
+        assert!((samples[25] - 1.0).abs() < 1e-3);
+        assert!((samples[75] + 1.0).abs() < 1e-3);
+        assert!((samples[100] - samples[0]).abs() < 1e-3);
+    }
+
+    #[test]
+    fn square() {
+        let samples = generate(Waveform::Square, 10.0, 100);
+
+        // Edges are subject to rounding of the phase.
+        assert!(samples[1..49].iter().all(|sample| *sample == 1.0));
+        assert!(samples[51..99].iter().all(|sample| *sample == -1.0));
+    }
+
+    #[test]
+    fn clicks() {
+        // One beat per second.
+        let samples = generate(Waveform::Clicks, 100.0, 2000);
+
+        assert!(samples[1..10].iter().any(|sample| sample.abs() > 0.5));
+        assert!(samples[10..1000].iter().all(|sample| *sample == 0.0));
+        assert!(samples[1001..1010].iter().any(|sample| sample.abs() > 0.5));
+    }
+
+    #[test]
+    fn noise_is_deterministic() {
+        for waveform in [Waveform::White, Waveform::Pink] {
+            let samples = generate(waveform, 1.0, 1000);
+
+            assert_eq!(samples, generate(waveform, 1.0, 1000));
+            assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+            assert!(samples.iter().any(|sample| *sample != 0.0));
+        }
+    }
+}
@@ -1,9 +1,18 @@
 pub mod audio;
+pub mod expr;
+pub mod filter;
 pub mod inputs;
 pub mod misc;
+pub mod onset;
+pub mod resample;
 pub mod spectrum;
+pub mod tempo;
 pub mod video;
 
+use std::time::Instant;
+
+use self::expr::ParseError;
+
 #[derive(Debug, Clone)]
 pub enum Error {
     System,
@@ -11,6 +20,7 @@ pub enum Error {
     InvalidOptions,
     UnknownNode(String),
     InvalidPipeline(InvalidPipeline),
+    InvalidExpression(ParseError),
 }
 
 #[derive(Debug, Clone)]
@@ -21,19 +31,37 @@ pub enum InvalidPipeline {
     InvalidSyntax,
 }
 
-// Can be used for caching where appropriate.
+// Can be used for caching where appropriate. In real-time mode, the frame also
+// carries the time at which it is presented.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
-pub struct FrameId(usize);
+pub struct FrameId {
+    id: usize,
+    time: Option<Instant>,
+}
 
 #[allow(clippy::new_without_default)]
 impl FrameId {
     pub fn new() -> Self {
-        Self(frame_id::get())
+        Self {
+            id: frame_id::get(),
+            time: None,
+        }
+    }
+
+    pub fn at(time: Instant) -> Self {
+        Self {
+            id: frame_id::get(),
+            time: Some(time),
+        }
+    }
+
+    pub fn time(&self) -> Option<Instant> {
+        self.time
     }
 
     pub fn update(&mut self, other: Self) -> bool {
-        if self.0 != other.0 {
-            self.0 = other.0;
+        if self.id != other.id {
+            *self = other;
             true
         } else {
             false
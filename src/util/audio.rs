@@ -1,32 +1,87 @@
 use std::{
+    collections::VecDeque,
     ops::Deref,
     sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
 };
 
+use crate::options::Options;
+
+use super::{Error, FrameId};
+
 pub const BUFFER_FRAMES: usize = 250;
 
+// Number of the most recent chunks whose arrival times are remembered.
+const TIMESTAMPS: usize = 64;
+
+// Samples are pushed interleaved and stored per channel. Multi-channel buffers
+// additionally store the mono mix of all channels, which is what the buffer
+// reads as unless a single channel is selected with `channel`.
+//
+// Each pushed chunk is timestamped with its position on the sample clock and
+// the time it arrived, so that the samples can be aligned with the presentation
+// time of a video frame.
 #[derive(Debug, Clone)]
 pub struct AudioBuffer {
-    buf: Arc<Mutex<Vec<f32>>>,
+    inner: Arc<Mutex<Inner>>,
+    track: usize,
+    channels: usize,
     frame_size: usize,
     sample_rate: usize,
     buf_size: usize,
+    latency: Duration,
+}
+
+#[derive(Debug)]
+struct Inner {
+    tracks: Vec<Vec<f32>>,
+    // Number of samples per channel pushed so far.
+    position: u64,
+    // Sample clock position at the end of a chunk and the time it arrived.
+    timestamps: VecDeque<(u64, Instant)>,
 }
 
 impl AudioBuffer {
     pub fn new(sample_rate: usize, fps: usize) -> Self {
+        Self::with_channels(sample_rate, fps, 1)
+    }
+
+    pub fn with_channels(sample_rate: usize, fps: usize, channels: usize) -> Self {
+        assert!(channels > 0, "no channels");
+
         let frame_size = sample_rate / fps;
         let buf_size = frame_size * BUFFER_FRAMES;
-        let buf = Arc::new(Mutex::new(Vec::with_capacity(2 * buf_size)));
+        let n_tracks = if channels > 1 { channels + 1 } else { 1 };
+        let tracks = (0..n_tracks)
+            .map(|_| Vec::with_capacity(2 * buf_size))
+            .collect();
+
+        let inner = Inner {
+            tracks,
+            position: 0,
+            timestamps: VecDeque::with_capacity(TIMESTAMPS),
+        };
 
         Self {
-            buf,
+            inner: Arc::new(Mutex::new(inner)),
+            // The mix is the last track.
+            track: n_tracks - 1,
+            channels,
             frame_size,
             sample_rate,
             buf_size,
+            latency: Duration::ZERO,
         }
     }
 
+    // The windows requested for a frame end earlier by the latency. Used to
+    // delay visuals after the audio they are computed from, e.g., to match the
+    // delay of an audio output.
+    pub fn with_latency(self, latency: Duration) -> Self {
+        Self { latency, ..self }
+    }
+
+    // Number of samples per channel corresponding to one video frame.
     pub fn frame_size(&self) -> usize {
         self.frame_size
     }
@@ -35,46 +90,379 @@ impl AudioBuffer {
         self.sample_rate
     }
 
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    // The returned buffer shares the data, but reads only the given channel.
+    pub fn channel(&self, channel: usize) -> Self {
+        assert!(channel < self.channels, "channel out of range");
+
+        Self {
+            track: channel,
+            ..self.clone()
+        }
+    }
+
     pub fn push(&self, data: &[f32]) {
-        assert!(data.len() <= self.buf_size, "unexpectedly large data chunk");
+        self.push_at(data, Instant::now());
+    }
+
+    // Pushes the data which arrived at the given time.
+    pub fn push_at(&self, data: &[f32], time: Instant) {
+        assert!(
+            data.len() % self.channels == 0,
+            "incomplete interleaved frame"
+        );
 
-        let mut buf = self.buf.lock().unwrap();
-        let buf_len = buf.len();
+        let len = data.len() / self.channels;
+        assert!(len <= self.buf_size, "unexpectedly large data chunk");
 
-        if buf_len + data.len() > 2 * self.buf_size {
-            let new_head = buf_len - self.buf_size;
-            buf.copy_within(new_head.., 0);
-            buf.resize(self.buf_size, 0.0);
+        let mut inner = self.inner.lock().unwrap();
+
+        for track in inner.tracks.iter_mut() {
+            make_room(track, len, self.buf_size);
         }
 
-        buf.extend_from_slice(data);
+        if self.channels == 1 {
+            inner.tracks[0].extend_from_slice(data);
+        } else {
+            for frame in data.chunks_exact(self.channels) {
+                for (channel, sample) in frame.iter().enumerate() {
+                    inner.tracks[channel].push(*sample);
+                }
+
+                inner.tracks[self.channels].push(frame.iter().sum::<f32>() / self.channels as f32);
+            }
+        }
+
+        inner.position += len as u64;
+
+        let position = inner.position;
+        if inner.timestamps.len() == TIMESTAMPS {
+            inner.timestamps.pop_front();
+        }
+        inner.timestamps.push_back((position, time));
     }
 
+    // The most recent samples.
     pub fn frames(&self, frames: usize) -> AudioDataGuard {
-        let buf = self.buf.lock().unwrap();
-        let len = (self.frame_size * frames).min(self.buf_size);
-        let head = buf.len().saturating_sub(len);
-        AudioDataGuard { buf, head }
+        self.exact(self.frame_size * frames)
     }
 
     pub fn exact(&self, n: usize) -> AudioDataGuard {
-        let buf = self.buf.lock().unwrap();
-        let len = n.min(self.buf_size);
-        let head = buf.len().saturating_sub(len);
-        AudioDataGuard { buf, head }
+        let inner = self.inner.lock().unwrap();
+        let end = inner.position;
+        self.window(inner, end, n)
+    }
+
+    // The samples ending at the presentation time of the frame, minus the
+    // latency. Frames without presentation time (offline mode) get the most
+    // recent samples, minus the latency.
+    pub fn frames_until(&self, id: FrameId, frames: usize) -> AudioDataGuard {
+        self.exact_until(id, self.frame_size * frames)
+    }
+
+    pub fn exact_until(&self, id: FrameId, n: usize) -> AudioDataGuard {
+        let inner = self.inner.lock().unwrap();
+        let end = self.position_until(&inner, id, self.latency);
+        self.window(inner, end, n)
+    }
+
+    // Sample clock position at the presentation time of the frame, without the
+    // latency.
+    pub fn output_position(&self, id: FrameId) -> u64 {
+        let inner = self.inner.lock().unwrap();
+        self.position_until(&inner, id, Duration::ZERO)
+    }
+
+    // Interleaved samples of all channels pushed after the given sample clock
+    // position, at most as many as fit in the buffer. Used by the nodes that
+    // process the stream sample by sample.
+    pub fn read_since(&self, position: u64) -> Chunk {
+        self.read_range(position, u64::MAX)
+    }
+
+    // Like `read_since`, but only up to the given end position.
+    pub fn read_range(&self, start: u64, end: u64) -> Chunk {
+        let inner = self.inner.lock().unwrap();
+        let end = end.min(inner.position);
+        let track_len = inner.tracks[0].len();
+        // Samples older than the stored ones are not available.
+        let tail = track_len - ((inner.position - end) as usize).min(track_len);
+        let stored = tail.min(self.buf_size) as u64;
+        let len = (end - start.min(end)).min(stored) as usize;
+
+        let mut samples = Vec::with_capacity(len * self.channels);
+        for i in (tail - len)..tail {
+            samples.extend((0..self.channels).map(|channel| inner.tracks[channel][i]));
+        }
+
+        // The pushed chunks ending within the samples, and the one containing
+        // the end.
+        let start = end - len as u64;
+        let mut timestamps = inner
+            .timestamps
+            .iter()
+            .copied()
+            .filter(|(position, _)| *position > start && *position < end)
+            .collect::<Vec<_>>();
+
+        if len > 0 {
+            if let Some((_, time)) = inner
+                .timestamps
+                .iter()
+                .find(|(position, _)| *position >= end)
+            {
+                timestamps.push((end, *time));
+            }
+        }
+
+        Chunk {
+            samples,
+            position: end,
+            timestamps,
+            channels: self.channels,
+        }
+    }
+
+    fn position_until(&self, inner: &Inner, id: FrameId, latency: Duration) -> u64 {
+        match id.time() {
+            Some(time) => {
+                let time = time.checked_sub(latency).unwrap_or(time);
+                self.position_at(inner, time)
+            }
+            None => {
+                let latency = latency.as_secs_f64() * self.sample_rate as f64;
+                inner.position.saturating_sub(latency as u64)
+            }
+        }
+    }
+
+    // Estimates the sample clock position at the given time by extrapolating
+    // from the latest chunk that arrived before. The samples in the future are
+    // not available yet, the position is therefore at most the current one.
+    fn position_at(&self, inner: &Inner, time: Instant) -> u64 {
+        let anchor = inner
+            .timestamps
+            .iter()
+            .rev()
+            .find(|(_, arrived)| *arrived <= time)
+            .or_else(|| inner.timestamps.front());
+
+        match anchor {
+            Some(&(position, arrived)) => {
+                let position = if time >= arrived {
+                    let elapsed = (time - arrived).as_secs_f64() * self.sample_rate as f64;
+                    position + elapsed as u64
+                } else {
+                    let early = (arrived - time).as_secs_f64() * self.sample_rate as f64;
+                    position.saturating_sub(early as u64)
+                };
+
+                position.min(inner.position)
+            }
+            None => inner.position,
+        }
+    }
+
+    fn window<'a>(&self, inner: MutexGuard<'a, Inner>, end: u64, n: usize) -> AudioDataGuard<'a> {
+        let track_len = inner.tracks[self.track].len();
+        // Samples older than the stored ones are not available.
+        let behind = ((inner.position - end) as usize).min(track_len);
+        let tail = track_len - behind;
+        let head = tail.saturating_sub(n.min(self.buf_size));
+
+        AudioDataGuard {
+            inner,
+            track: self.track,
+            head,
+            tail,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Chunk {
+    pub samples: Vec<f32>,
+    // Sample clock position at the end of the samples.
+    pub position: u64,
+    // Sample clock position at the end of each pushed chunk the samples come
+    // from, and the time it arrived.
+    timestamps: Vec<(u64, Instant)>,
+    channels: usize,
+}
+
+impl Chunk {
+    // The samples split by the pushed chunks they come from, with the times
+    // they arrived. Used for pushing processed samples with the original
+    // timestamps.
+    pub fn parts(&self) -> impl Iterator<Item = (&[f32], Instant)> + '_ {
+        let first = self.position - (self.samples.len() / self.channels) as u64;
+        let mut head = 0;
+
+        self.timestamps.iter().map(move |(end, time)| {
+            let tail = (end - first) as usize * self.channels;
+            let part = &self.samples[head..tail];
+            head = tail;
+            (part, *time)
+        })
     }
 }
 
+// Parses the `latency-ms` option common to all audio sources.
+pub fn get_latency(options: &Options) -> Result<Duration, Error> {
+    let latency = options
+        .get("latency-ms")
+        .unwrap_or(&0.0.into())
+        .as_f32()
+        .filter(|latency| *latency >= 0.0)
+        .ok_or(Error::InvalidOptions)?;
+
+    Ok(Duration::from_secs_f32(latency / 1000.0))
+}
+
+fn make_room(track: &mut Vec<f32>, len: usize, buf_size: usize) {
+    let track_len = track.len();
+
+    if track_len + len > 2 * buf_size {
+        let new_head = track_len - buf_size;
+        track.copy_within(new_head.., 0);
+        track.resize(buf_size, 0.0);
+    }
+}
+
+// Holds the lock of the buffer. It must be dropped before the buffer (or any of
+// its clones) is used again on the same thread, otherwise it deadlocks.
 #[derive(Debug)]
 pub struct AudioDataGuard<'a> {
-    buf: MutexGuard<'a, Vec<f32>>,
+    inner: MutexGuard<'a, Inner>,
+    track: usize,
     head: usize,
+    tail: usize,
 }
 
 impl<'a> Deref for AudioDataGuard<'a> {
     type Target = [f32];
 
     fn deref(&self) -> &Self::Target {
-        &self.buf[self.head..]
+        &self.inner.tracks[self.track][self.head..self.tail]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One frame per 10 samples.
+    fn buffer() -> AudioBuffer {
+        AudioBuffer::new(100, 10)
+    }
+
+    #[test]
+    fn channels() {
+        let buf = AudioBuffer::with_channels(100, 10, 2);
+        buf.push(&[1.0, 0.0, 0.5, 0.5]);
+
+        assert_eq!(&*buf.exact(2), &[0.5, 0.5]);
+        assert_eq!(&*buf.channel(0).exact(2), &[1.0, 0.5]);
+        assert_eq!(&*buf.channel(1).exact(2), &[0.0, 0.5]);
+    }
+
+    #[test]
+    fn latency_without_presentation_time() {
+        let buf = buffer().with_latency(Duration::from_millis(100));
+        let data = (0..30).map(|x| x as f32).collect::<Vec<_>>();
+        buf.push(&data);
+
+        // 100 ms is 10 samples.
+        let expected = (10..20).map(|x| x as f32).collect::<Vec<_>>();
+        assert_eq!(&*buf.frames_until(FrameId::new(), 1), expected.as_slice());
+
+        // The output is not delayed.
+        assert_eq!(buf.output_position(FrameId::new()), 30);
+    }
+
+    #[test]
+    fn window_at_presentation_time() {
+        let buf = buffer();
+        let start = Instant::now();
+
+        for i in 0..3 {
+            let data = (0..10).map(|x| (10 * i + x) as f32).collect::<Vec<_>>();
+            buf.push_at(&data, start + Duration::from_millis(100 * i as u64));
+        }
+
+        // The second chunk arrived at 100 ms.
+        let expected = (10..20).map(|x| x as f32).collect::<Vec<_>>();
+        assert_eq!(
+            &*buf.frames_until(FrameId::at(start + Duration::from_millis(100)), 1),
+            expected.as_slice()
+        );
+
+        // Half-way between the second and third chunk.
+        let expected = (20..25).map(|x| x as f32).collect::<Vec<_>>();
+        assert_eq!(
+            &*buf.exact_until(FrameId::at(start + Duration::from_millis(150)), 5),
+            expected.as_slice()
+        );
+
+        // The future is not available.
+        let expected = (20..30).map(|x| x as f32).collect::<Vec<_>>();
+        assert_eq!(
+            &*buf.frames_until(FrameId::at(start + Duration::from_secs(10)), 1),
+            expected.as_slice()
+        );
+    }
+
+    #[test]
+    fn read_since() {
+        let buf = AudioBuffer::with_channels(100, 10, 2);
+        buf.push(&[1.0, 2.0, 3.0, 4.0]);
+
+        let chunk = buf.read_since(0);
+        assert_eq!(chunk.samples, [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(chunk.position, 2);
+
+        buf.push(&[5.0, 6.0]);
+
+        let chunk = buf.read_since(chunk.position);
+        assert_eq!(chunk.samples, [5.0, 6.0]);
+        assert_eq!(chunk.position, 3);
+
+        let chunk = buf.read_range(1, 2);
+        assert_eq!(chunk.samples, [3.0, 4.0]);
+        assert_eq!(chunk.position, 2);
+
+        // Nothing after the end.
+        assert!(buf.read_range(2, 1).samples.is_empty());
+    }
+
+    #[test]
+    fn parts() {
+        let buf = AudioBuffer::with_channels(100, 10, 2);
+        let start = Instant::now();
+        let later = start + Duration::from_millis(20);
+
+        buf.push_at(&[1.0, 2.0, 3.0, 4.0], start);
+        buf.push_at(&[5.0, 6.0, 7.0, 8.0], later);
+
+        let chunk = buf.read_since(1);
+        let parts = chunk.parts().collect::<Vec<_>>();
+        assert_eq!(
+            parts,
+            [(&[3.0, 4.0][..], start), (&[5.0, 6.0, 7.0, 8.0][..], later)]
+        );
+
+        let chunk = buf.read_range(0, 3);
+        let parts = chunk.parts().collect::<Vec<_>>();
+        assert_eq!(
+            parts,
+            [(&[1.0, 2.0, 3.0, 4.0][..], start), (&[5.0, 6.0][..], later)]
+        );
     }
 }
@@ -0,0 +1,378 @@
+use std::iter::Peekable;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    // Column of the offending character, starting at 1.
+    pub position: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position: position + 1,
+            message: message.into(),
+        }
+    }
+}
+
+// Arithmetic expression with variables, parsed once and evaluated many times.
+// The variables are resolved to indices into the values passed to `eval`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    root: Ast,
+}
+
+impl Expr {
+    pub fn parse(source: &str, variables: &[&str]) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?.into_iter().peekable(),
+            variables,
+            end: source.chars().count(),
+        };
+
+        let root = parser.expr()?;
+
+        match parser.tokens.next() {
+            Some((position, _)) => Err(ParseError::new(position, "unexpected token")),
+            None => Ok(Self { root }),
+        }
+    }
+
+    pub fn eval(&self, values: &[f32]) -> f32 {
+        self.root.eval(values)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Ast {
+    Number(f32),
+    Variable(usize),
+    Neg(Box<Ast>),
+    Binary(Op, Box<Ast>, Box<Ast>),
+    Call(Function, Vec<Ast>),
+}
+
+impl Ast {
+    fn eval(&self, values: &[f32]) -> f32 {
+        match self {
+            Ast::Number(x) => *x,
+            Ast::Variable(index) => values[*index],
+            Ast::Neg(x) => -x.eval(values),
+            Ast::Binary(op, a, b) => op.apply(a.eval(values), b.eval(values)),
+            Ast::Call(function, args) => {
+                // No allocation per call, the arity is checked by the parser.
+                let mut evaluated = [0.0; MAX_ARITY];
+                for (value, arg) in evaluated.iter_mut().zip(args.iter()) {
+                    *value = arg.eval(values);
+                }
+                function.apply(&evaluated[..args.len()])
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+impl Op {
+    fn apply(&self, a: f32, b: f32) -> f32 {
+        match self {
+            Op::Add => a + b,
+            Op::Sub => a - b,
+            Op::Mul => a * b,
+            Op::Div => a / b,
+            Op::Rem => a % b,
+            Op::Pow => a.powf(b),
+        }
+    }
+}
+
+// Largest number of arguments of a function.
+const MAX_ARITY: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Sin,
+    Cos,
+    Abs,
+    Sqrt,
+    Min,
+    Max,
+    Pow,
+    Clamp,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sin" => Some(Function::Sin),
+            "cos" => Some(Function::Cos),
+            "abs" => Some(Function::Abs),
+            "sqrt" => Some(Function::Sqrt),
+            "min" => Some(Function::Min),
+            "max" => Some(Function::Max),
+            "pow" => Some(Function::Pow),
+            "clamp" => Some(Function::Clamp),
+            _ => None,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Function::Sin | Function::Cos | Function::Abs | Function::Sqrt => 1,
+            Function::Min | Function::Max | Function::Pow => 2,
+            Function::Clamp => 3,
+        }
+    }
+
+    fn apply(&self, args: &[f32]) -> f32 {
+        match self {
+            Function::Sin => args[0].sin(),
+            Function::Cos => args[0].cos(),
+            Function::Abs => args[0].abs(),
+            Function::Sqrt => args[0].sqrt(),
+            Function::Min => args[0].min(args[1]),
+            Function::Max => args[0].max(args[1]),
+            Function::Pow => args[0].powf(args[1]),
+            // Unlike `f32::clamp`, does not panic on an invalid range.
+            Function::Clamp => args[0].max(args[1]).min(args[2]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    Ident(String),
+    Op(Op),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = Vec::new();
+    // Positions are in characters rather than bytes.
+    let mut chars = source.chars().enumerate().peekable();
+
+    while let Some((position, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '/' => Token::Op(Op::Div),
+            '%' => Token::Op(Op::Rem),
+            '^' => Token::Op(Op::Pow),
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            ',' => Token::Comma,
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::from(c);
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+
+                let number = number
+                    .parse()
+                    .map_err(|_| ParseError::new(position, "invalid number"))?;
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::from(c);
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                Token::Ident(ident)
+            }
+            _ => return Err(ParseError::new(position, "unexpected character")),
+        };
+
+        tokens.push((position, token));
+    }
+
+    Ok(tokens)
+}
+
+// Recursive descent, from the lowest precedence:
+//
+//   expr    = term (("+" | "-") term)*
+//   term    = unary (("*" | "/" | "%") unary)*
+//   unary   = "-" unary | power
+//   power   = primary ("^" unary)?
+//   primary = number | ident | ident "(" expr ("," expr)* ")" | "(" expr ")"
+struct Parser<'a> {
+    tokens: Peekable<std::vec::IntoIter<(usize, Token)>>,
+    variables: &'a [&'a str],
+    // Position reported for an unexpected end.
+    end: usize,
+}
+
+impl Parser<'_> {
+    fn expr(&mut self) -> Result<Ast, ParseError> {
+        let mut ast = self.term()?;
+
+        while let Some(op) = self.next_op(&[Op::Add, Op::Sub]) {
+            ast = Ast::Binary(op, Box::new(ast), Box::new(self.term()?));
+        }
+
+        Ok(ast)
+    }
+
+    fn term(&mut self) -> Result<Ast, ParseError> {
+        let mut ast = self.unary()?;
+
+        while let Some(op) = self.next_op(&[Op::Mul, Op::Div, Op::Rem]) {
+            ast = Ast::Binary(op, Box::new(ast), Box::new(self.unary()?));
+        }
+
+        Ok(ast)
+    }
+
+    fn unary(&mut self) -> Result<Ast, ParseError> {
+        if self.next_op(&[Op::Sub]).is_some() {
+            Ok(Ast::Neg(Box::new(self.unary()?)))
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<Ast, ParseError> {
+        let base = self.primary()?;
+
+        // Right associative, `2^3^2` is `2^(3^2)`.
+        if self.next_op(&[Op::Pow]).is_some() {
+            Ok(Ast::Binary(
+                Op::Pow,
+                Box::new(base),
+                Box::new(self.unary()?),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self) -> Result<Ast, ParseError> {
+        match self.tokens.next() {
+            Some((_, Token::Number(x))) => Ok(Ast::Number(x)),
+            Some((position, Token::Ident(name))) => {
+                if self.next_is(&Token::LeftParen) {
+                    self.call(position, &name)
+                } else {
+                    self.variables
+                        .iter()
+                        .position(|variable| *variable == name)
+                        .map(Ast::Variable)
+                        .ok_or_else(|| {
+                            ParseError::new(position, format!("unknown variable `{name}`"))
+                        })
+                }
+            }
+            Some((_, Token::LeftParen)) => {
+                let ast = self.expr()?;
+                self.expect(Token::RightParen, "expected `)`")?;
+                Ok(ast)
+            }
+            Some((position, _)) => Err(ParseError::new(position, "expected a value")),
+            None => Err(ParseError::new(self.end, "unexpected end")),
+        }
+    }
+
+    // The opening parenthesis is already consumed.
+    fn call(&mut self, position: usize, name: &str) -> Result<Ast, ParseError> {
+        let function = Function::from_name(name)
+            .ok_or_else(|| ParseError::new(position, format!("unknown function `{name}`")))?;
+
+        let mut args = vec![self.expr()?];
+        while self.next_is(&Token::Comma) {
+            args.push(self.expr()?);
+        }
+
+        self.expect(Token::RightParen, "expected `,` or `)`")?;
+
+        if args.len() != function.arity() {
+            return Err(ParseError::new(
+                position,
+                format!("`{name}` takes {} argument(s)", function.arity()),
+            ));
+        }
+
+        Ok(Ast::Call(function, args))
+    }
+
+    fn next_op(&mut self, ops: &[Op]) -> Option<Op> {
+        match self.tokens.peek() {
+            Some((_, Token::Op(op))) if ops.contains(op) => {
+                let op = *op;
+                self.tokens.next();
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn next_is(&mut self, token: &Token) -> bool {
+        self.tokens.next_if(|(_, next)| next == token).is_some()
+    }
+
+    fn expect(&mut self, token: Token, message: &str) -> Result<(), ParseError> {
+        match self.tokens.next() {
+            Some((_, next)) if next == token => Ok(()),
+            Some((position, _)) => Err(ParseError::new(position, message)),
+            None => Err(ParseError::new(self.end, message)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str) -> f32 {
+        Expr::parse(source, &["x", "y"]).unwrap().eval(&[2.0, 3.0])
+    }
+
+    fn error(source: &str) -> ParseError {
+        Expr::parse(source, &["x", "y"]).unwrap_err()
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3"), 7.0);
+        assert_eq!(eval("(1 + 2) * 3"), 9.0);
+        assert_eq!(eval("-x ^ 2"), -4.0);
+        assert_eq!(eval("2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(eval("10 - 4 - 3"), 3.0);
+        assert_eq!(eval("7 % 4 / 2"), 1.5);
+    }
+
+    #[test]
+    fn variables_and_functions() {
+        assert_eq!(
+            eval("0.2 + 0.8 * x * (1 - y)"),
+            0.2 + 0.8 * 2.0 * (1.0 - 3.0)
+        );
+        assert_eq!(eval("max(x, y) - min(x, y)"), 1.0);
+        assert_eq!(eval("clamp(x * y, 0, 1)"), 1.0);
+        assert_eq!(eval("abs(sin(0)) + sqrt(pow(y, 2))"), 3.0);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("1 +").position, 4);
+        assert_eq!(error("1 + z").position, 5);
+        assert_eq!(error("foo(1)").position, 1);
+        assert_eq!(error("min(1)").position, 1);
+        assert_eq!(error("(1 + 2").position, 7);
+        assert_eq!(error("1 2").position, 3);
+        assert_eq!(error("1 # 2").position, 3);
+    }
+}
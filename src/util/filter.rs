@@ -0,0 +1,219 @@
+use std::f32::consts::PI;
+
+use crate::options::Value;
+
+use super::Error;
+
+// https://www.w3.org/TR/audio-eq-cookbook/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterType {
+    Lowpass,
+    Highpass,
+    Bandpass,
+    Notch,
+    Peak,
+    LowShelf,
+    HighShelf,
+}
+
+impl FilterType {
+    pub fn from_value(value: &Value) -> Result<Self, Error> {
+        match value.as_str() {
+            Some("lowpass") => Ok(FilterType::Lowpass),
+            Some("highpass") => Ok(FilterType::Highpass),
+            Some("bandpass") => Ok(FilterType::Bandpass),
+            Some("notch") => Ok(FilterType::Notch),
+            Some("peak") => Ok(FilterType::Peak),
+            Some("lowshelf") => Ok(FilterType::LowShelf),
+            Some("highshelf") => Ok(FilterType::HighShelf),
+            _ => Err(Error::InvalidOptions),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coefficients {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl Coefficients {
+    // The gain (in dB) is used only by the peak and shelving filters.
+    pub fn new(
+        filter_type: FilterType,
+        sample_rate: usize,
+        frequency: f32,
+        q: f32,
+        gain: f32,
+    ) -> Self {
+        let w0 = 2.0 * PI * frequency / sample_rate as f32;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let a = 10f32.powf(gain / 40.0);
+
+        let (b0, b1, b2, a0, a1, a2) = match filter_type {
+            FilterType::Lowpass => (
+                (1.0 - cos) / 2.0,
+                1.0 - cos,
+                (1.0 - cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            FilterType::Highpass => (
+                (1.0 + cos) / 2.0,
+                -(1.0 + cos),
+                (1.0 + cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            // Constant 0 dB peak gain.
+            FilterType::Bandpass => (alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos, 1.0 - alpha),
+            FilterType::Notch => (1.0, -2.0 * cos, 1.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha),
+            FilterType::Peak => (
+                1.0 + alpha * a,
+                -2.0 * cos,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ),
+            FilterType::LowShelf => {
+                let beta = 2.0 * a.sqrt() * alpha;
+                (
+                    a * ((a + 1.0) - (a - 1.0) * cos + beta),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                    a * ((a + 1.0) - (a - 1.0) * cos - beta),
+                    (a + 1.0) + (a - 1.0) * cos + beta,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                    (a + 1.0) + (a - 1.0) * cos - beta,
+                )
+            }
+            FilterType::HighShelf => {
+                let beta = 2.0 * a.sqrt() * alpha;
+                (
+                    a * ((a + 1.0) + (a - 1.0) * cos + beta),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                    a * ((a + 1.0) + (a - 1.0) * cos - beta),
+                    (a + 1.0) - (a - 1.0) * cos + beta,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                    (a + 1.0) - (a - 1.0) * cos - beta,
+                )
+            }
+        };
+
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
+// Transposed direct form II, the state carries over between the calls of
+// `process` so that the signal can be filtered in chunks.
+#[derive(Debug, Clone)]
+pub struct Biquad {
+    coefs: Coefficients,
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    pub fn new(coefs: Coefficients) -> Self {
+        Self {
+            coefs,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        let c = &self.coefs;
+        let y = c.b0 * x + self.z1;
+        self.z1 = c.b1 * x - c.a1 * y + self.z2;
+        self.z2 = c.b2 * x - c.a2 * y;
+        y
+    }
+
+    pub fn process(&mut self, data: &mut [f32]) {
+        for x in data.iter_mut() {
+            *x = self.process_sample(*x);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: usize = 44100;
+
+    // Gain of the filter for a sine of the given frequency, after the
+    // transient response settled.
+    fn gain(filter_type: FilterType, cutoff: f32, gain: f32, frequency: f32) -> f32 {
+        let coefs = Coefficients::new(filter_type, SAMPLE_RATE, cutoff, 0.707, gain);
+        let mut filter = Biquad::new(coefs);
+
+        let mut data = (0..SAMPLE_RATE)
+            .map(|i| (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect::<Vec<_>>();
+
+        // Processing in chunks must not make a difference.
+        for chunk in data.chunks_mut(1000) {
+            filter.process(chunk);
+        }
+
+        let tail = &data[SAMPLE_RATE / 2..];
+        let rms = (tail.iter().map(|x| x * x).sum::<f32>() / tail.len() as f32).sqrt();
+        rms * 2f32.sqrt()
+    }
+
+    #[test]
+    fn lowpass() {
+        assert!(gain(FilterType::Lowpass, 1000.0, 0.0, 100.0) > 0.95);
+        assert!(gain(FilterType::Lowpass, 1000.0, 0.0, 10000.0) < 0.05);
+    }
+
+    #[test]
+    fn highpass() {
+        assert!(gain(FilterType::Highpass, 1000.0, 0.0, 100.0) < 0.05);
+        assert!(gain(FilterType::Highpass, 1000.0, 0.0, 10000.0) > 0.95);
+    }
+
+    #[test]
+    fn bandpass() {
+        assert!((gain(FilterType::Bandpass, 1000.0, 0.0, 1000.0) - 1.0).abs() < 0.05);
+        assert!(gain(FilterType::Bandpass, 1000.0, 0.0, 100.0) < 0.2);
+        assert!(gain(FilterType::Bandpass, 1000.0, 0.0, 10000.0) < 0.2);
+    }
+
+    #[test]
+    fn notch() {
+        assert!(gain(FilterType::Notch, 1000.0, 0.0, 1000.0) < 0.05);
+        assert!(gain(FilterType::Notch, 1000.0, 0.0, 10000.0) > 0.95);
+    }
+
+    #[test]
+    fn peak() {
+        // +6 dB is double the amplitude.
+        let boosted = gain(FilterType::Peak, 1000.0, 6.0, 1000.0);
+        assert!((boosted - 2.0).abs() < 0.05);
+        assert!((gain(FilterType::Peak, 1000.0, 6.0, 15000.0) - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn shelves() {
+        assert!((gain(FilterType::LowShelf, 1000.0, 6.0, 50.0) - 2.0).abs() < 0.05);
+        assert!((gain(FilterType::LowShelf, 1000.0, 6.0, 15000.0) - 1.0).abs() < 0.05);
+
+        assert!((gain(FilterType::HighShelf, 1000.0, -6.0, 50.0) - 1.0).abs() < 0.05);
+        assert!((gain(FilterType::HighShelf, 1000.0, -6.0, 15000.0) - 0.5).abs() < 0.05);
+    }
+}
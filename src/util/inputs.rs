@@ -30,6 +30,14 @@ fn check_slice(caps: &[Capability], input: &NodeRef) -> bool {
     caps.iter().copied().any(|cap| input.has_capability(cap))
 }
 
+// Marks an input that does not need to be provided.
+#[derive(Debug, Clone, Copy)]
+pub struct Optional<T>(pub T);
+
+// Any number of inputs (including none), all of them validated the same.
+#[derive(Debug, Clone, Copy)]
+pub struct Many<T>(pub T);
+
 pub trait Validate: private::Sealed {
     type Validated;
 
@@ -112,8 +120,63 @@ where
     }
 }
 
+impl<T1, T2> Validate for (T1, Optional<T2>)
+where
+    T1: Validator,
+    T2: Validator,
+{
+    type Validated = (NodeRef, Option<NodeRef>);
+
+    fn validate<I: IntoIterator<Item = NodeRef>>(
+        &self,
+        inputs: I,
+    ) -> Result<Self::Validated, Error> {
+        let mut inputs = inputs.into_iter();
+
+        let first = inputs.next().ok_or(Error::InvalidInputs)?;
+        if !self.0.check(&first) {
+            return Err(Error::InvalidInputs);
+        }
+
+        let second = inputs.next();
+        if let Some(second) = second.as_ref() {
+            if !self.1 .0.check(second) {
+                return Err(Error::InvalidInputs);
+            }
+        }
+
+        if inputs.next().is_some() {
+            return Err(Error::InvalidInputs);
+        }
+
+        Ok((first, second))
+    }
+}
+
+impl<T> Validate for Many<T>
+where
+    T: Validator,
+{
+    type Validated = Vec<NodeRef>;
+
+    fn validate<I: IntoIterator<Item = NodeRef>>(
+        &self,
+        inputs: I,
+    ) -> Result<Self::Validated, Error> {
+        inputs
+            .into_iter()
+            .map(|input| {
+                self.0
+                    .check(&input)
+                    .then_some(input)
+                    .ok_or(Error::InvalidInputs)
+            })
+            .collect()
+    }
+}
+
 mod private {
-    use super::Validator;
+    use super::{Many, Optional, Validator};
 
     pub trait Sealed {}
 
@@ -125,4 +188,11 @@ mod private {
         T2: Validator,
     {
     }
+    impl<T1, T2> Sealed for (T1, Optional<T2>)
+    where
+        T1: Validator,
+        T2: Validator,
+    {
+    }
+    impl<T> Sealed for Many<T> where T: Validator {}
 }
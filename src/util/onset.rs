@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+
+// Compression of the magnitudes so that quiet onsets count as well.
+const COMPRESSION: f32 = 100.0;
+
+// Flux below which nothing is considered an onset, e.g., in silence.
+const MIN_FLUX: f32 = 1e-3;
+
+// Sum of the increases of the (log-compressed) magnitudes between consecutive
+// spectra, normalized by the number of bins.
+#[derive(Debug, Default)]
+pub struct SpectralFlux {
+    previous: Vec<f32>,
+}
+
+impl SpectralFlux {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn compute<I>(&mut self, magnitudes: I) -> f32
+    where
+        I: IntoIterator<Item = f32>,
+    {
+        let current = magnitudes
+            .into_iter()
+            .map(|magnitude| (1.0 + COMPRESSION * magnitude).ln())
+            .collect::<Vec<_>>();
+
+        // The first spectrum (or a change of the bins) has nothing to be
+        // compared with.
+        let flux = if self.previous.len() == current.len() && !current.is_empty() {
+            let increase = current
+                .iter()
+                .zip(self.previous.iter())
+                .map(|(current, previous)| (current - previous).max(0.0))
+                .sum::<f32>();
+            increase / current.len() as f32
+        } else {
+            0.0
+        };
+
+        self.previous = current;
+        flux
+    }
+}
+
+// Detects onsets as the flux exceeding a threshold adapted to the recent
+// history, with at least `min_interval` frames between the onsets.
+#[derive(Debug)]
+pub struct OnsetDetector {
+    history: VecDeque<f32>,
+    history_len: usize,
+    // Number of standard deviations above the mean.
+    deviations: f32,
+    min_interval: usize,
+    since_onset: usize,
+}
+
+impl OnsetDetector {
+    // Sensitivity is from 0 to 1, the higher the more onsets are detected.
+    pub fn new(history_len: usize, sensitivity: f32, min_interval: usize) -> Self {
+        Self {
+            history: VecDeque::with_capacity(history_len),
+            history_len,
+            deviations: 0.5 + 3.0 * (1.0 - sensitivity),
+            min_interval,
+            since_onset: usize::MAX,
+        }
+    }
+
+    pub fn detect(&mut self, flux: f32) -> bool {
+        let n = self.history.len().max(1) as f32;
+        let mean = self.history.iter().sum::<f32>() / n;
+        let variance = self
+            .history
+            .iter()
+            .map(|x| (x - mean) * (x - mean))
+            .sum::<f32>()
+            / n;
+        let threshold = mean + self.deviations * variance.sqrt();
+
+        if self.history.len() == self.history_len {
+            self.history.pop_front();
+        }
+        self.history.push_back(flux);
+
+        self.since_onset = self.since_onset.saturating_add(1);
+
+        let onset = flux > threshold.max(MIN_FLUX) && self.since_onset >= self.min_interval;
+        if onset {
+            self.since_onset = 0;
+        }
+
+        onset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flux_of_increase_only() {
+        let mut flux = SpectralFlux::new();
+
+        assert_eq!(flux.compute([0.0, 0.0]), 0.0);
+        assert!(flux.compute([1.0, 0.0]) > 0.0);
+        // Decrease does not count.
+        assert_eq!(flux.compute([0.0, 0.0]), 0.0);
+    }
+
+    // Pulses every 10 frames on a noisy floor.
+    fn pulses(frames: usize) -> impl Iterator<Item = (usize, f32)> {
+        (0..frames).map(|i| {
+            let noise = 0.01 * ((i * 7919) % 13) as f32 / 13.0;
+            let pulse = if i % 10 == 0 { 1.0 } else { 0.0 };
+            (i, noise + pulse)
+        })
+    }
+
+    #[test]
+    fn detects_pulses() {
+        let mut detector = OnsetDetector::new(30, 0.5, 1);
+
+        let onsets = pulses(100)
+            .filter(|(_, flux)| detector.detect(*flux))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        assert_eq!(onsets, (0..100).step_by(10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn min_interval() {
+        let mut detector = OnsetDetector::new(30, 0.5, 15);
+        let onsets = pulses(100)
+            .filter(|(_, flux)| detector.detect(*flux))
+            .count();
+
+        assert_eq!(onsets, 5);
+    }
+}
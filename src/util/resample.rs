@@ -0,0 +1,185 @@
+use super::filter::{Biquad, Coefficients, FilterType};
+
+// Cutoff of the anti-aliasing filter relative to the target sample rate.
+const CUTOFF: f32 = 0.45;
+
+// Butterworth Q factors of the two sections of a 4th order low-pass.
+const Q: [f32; 2] = [0.541, 1.307];
+
+// Converts a stream of interleaved samples to a different sample rate and
+// number of channels. Processes the stream in chunks of arbitrary size.
+#[derive(Debug)]
+pub struct Resampler {
+    source_channels: usize,
+    target_channels: usize,
+    // Source samples per target sample.
+    step: f64,
+    // Position of the next target sample, relative to the last source frame of
+    // the previous chunk.
+    position: f64,
+    last: Vec<f32>,
+    // Two sections per target channel, only when downsampling.
+    filters: Vec<[Biquad; 2]>,
+    frames: Vec<f32>,
+}
+
+impl Resampler {
+    pub fn new(
+        source_rate: usize,
+        source_channels: usize,
+        target_rate: usize,
+        target_channels: usize,
+    ) -> Self {
+        let filters = if target_rate < source_rate {
+            let frequency = CUTOFF * target_rate as f32;
+            let section = |q| {
+                Biquad::new(Coefficients::new(
+                    FilterType::Lowpass,
+                    source_rate,
+                    frequency,
+                    q,
+                    0.0,
+                ))
+            };
+
+            (0..target_channels)
+                .map(|_| [section(Q[0]), section(Q[1])])
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            source_channels,
+            target_channels,
+            step: source_rate as f64 / target_rate as f64,
+            // The first target sample is the first source sample.
+            position: 1.0,
+            last: vec![0.0; target_channels],
+            filters,
+            frames: Vec::new(),
+        }
+    }
+
+    // Appends the converted samples to `output`.
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        // Channels are converted first, so that the rest is done on the target
+        // channels.
+        self.frames.clear();
+        for frame in input.chunks_exact(self.source_channels) {
+            for channel in 0..self.target_channels {
+                let sample = self.map_channel(frame, channel);
+                self.frames.push(sample);
+            }
+        }
+
+        for frame in self.frames.chunks_exact_mut(self.target_channels) {
+            for (sample, sections) in frame.iter_mut().zip(self.filters.iter_mut()) {
+                for section in sections.iter_mut() {
+                    *sample = section.process_sample(*sample);
+                }
+            }
+        }
+
+        // Linear interpolation. Index 0 is the last frame of the previous
+        // chunk.
+        let n = self.frames.len() / self.target_channels;
+        let channels = self.target_channels;
+        let frame = |i: usize, channel: usize| match i {
+            0 => self.last[channel],
+            i => self.frames[(i - 1) * channels + channel],
+        };
+
+        while self.position < n as f64 {
+            let i = self.position as usize;
+            let frac = (self.position - i as f64) as f32;
+
+            for channel in 0..channels {
+                let sample = frame(i, channel) * (1.0 - frac) + frame(i + 1, channel) * frac;
+                output.push(sample);
+            }
+
+            self.position += self.step;
+        }
+
+        self.position -= n as f64;
+        if n > 0 {
+            self.last
+                .copy_from_slice(&self.frames[(n - 1) * channels..n * channels]);
+        }
+    }
+
+    // Mono is the mix of all channels. Otherwise, the source channels are
+    // taken in order, repeating the last one if there are not enough.
+    fn map_channel(&self, frame: &[f32], channel: usize) -> f32 {
+        if self.target_channels == 1 {
+            frame.iter().sum::<f32>() / frame.len() as f32
+        } else {
+            frame[channel.min(frame.len() - 1)]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    fn resample(resampler: &mut Resampler, input: &[f32], chunk_size: usize) -> Vec<f32> {
+        let mut output = Vec::new();
+        for chunk in input.chunks(chunk_size) {
+            resampler.process(chunk, &mut output);
+        }
+        output
+    }
+
+    // The last source frame is held back until the next chunk arrives.
+    #[test]
+    fn length() {
+        let input = vec![0.0; 48000];
+
+        let mut resampler = Resampler::new(48000, 1, 44100, 1);
+        let len = resample(&mut resampler, &input, 1600).len();
+        assert!((44098..=44100).contains(&len));
+
+        let mut resampler = Resampler::new(48000, 1, 96000, 1);
+        assert_eq!(resample(&mut resampler, &input, 1600).len(), 95998);
+    }
+
+    #[test]
+    fn channels() {
+        let mut resampler = Resampler::new(44100, 2, 44100, 1);
+        let input = [1.0, 0.0, 1.0, 0.0, 1.0, 0.0];
+        assert_eq!(resample(&mut resampler, &input, 2), [0.5, 0.5]);
+
+        let mut resampler = Resampler::new(44100, 1, 44100, 2);
+        let input = [1.0, 0.0, 1.0];
+        assert_eq!(resample(&mut resampler, &input, 1), [1.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn interpolation() {
+        let input = (0..10).map(|x| x as f32).collect::<Vec<_>>();
+        let mut resampler = Resampler::new(1000, 1, 2000, 1);
+        let output = resample(&mut resampler, &input, 3);
+
+        let expected = (0..18).map(|x| x as f32 / 2.0).collect::<Vec<_>>();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn anti_aliasing() {
+        // Above the Nyquist frequency of the target rate.
+        let input = (0..48000)
+            .map(|i| (2.0 * PI * 15000.0 * i as f32 / 48000.0).sin())
+            .collect::<Vec<_>>();
+
+        let mut resampler = Resampler::new(48000, 1, 16000, 1);
+        let output = resample(&mut resampler, &input, 1600);
+
+        let tail = &output[8000..];
+        let rms = (tail.iter().map(|x| x * x).sum::<f32>() / tail.len() as f32).sqrt();
+        assert!(rms < 0.05);
+    }
+}
@@ -8,6 +8,7 @@ pub struct SpectrumStore {
     window_len: usize,
     last_id: FrameId,
     frequency_range: Option<(f32, f32)>,
+    padded: Vec<f32>,
 }
 
 impl SpectrumStore {
@@ -40,20 +41,32 @@ impl SpectrumStore {
             window_len,
             last_id: FrameId::default(),
             frequency_range,
+            padded: Vec::with_capacity(window_len),
         }
     }
 
     pub fn compute(&mut self, id: FrameId, data: &[f32], sample_rate: usize) -> Spectrum {
         let spectrum = if self.last_id.update(id) {
-            self.spectrum.compute(data)
+            if data.len() < self.window_len {
+                // Not enough samples yet, e.g., on the first frames, the start
+                // of the window is silent.
+                self.padded.clear();
+                self.padded.resize(self.window_len - data.len(), 0.0);
+                self.padded.extend_from_slice(data);
+                self.spectrum.compute(&self.padded)
+            } else {
+                self.spectrum.compute(data)
+            }
         } else {
             self.spectrum.get()
         };
 
-        let orig_len = spectrum.len();
+        let window_len = self.window_len;
         let (spectrum, bin0, f_min, f_max) = if let Some((f_min, f_max)) = self.frequency_range {
-            let bin0 = bin_for(orig_len, f_min, sample_rate);
-            let spectrum = &spectrum[bin0..bin_for(orig_len, f_max, sample_rate)];
+            // Frequencies above the Nyquist frequency have no bins.
+            let bin1 = bin_for(window_len, f_max, sample_rate).min(spectrum.len());
+            let bin0 = bin_for(window_len, f_min, sample_rate).min(bin1);
+            let spectrum = &spectrum[bin0..bin1];
             (spectrum, bin0, f_min, f_max)
         } else {
             (spectrum, 0, 0.0, f32::INFINITY)
@@ -62,8 +75,9 @@ impl SpectrumStore {
         Spectrum {
             spectrum: spectrum.to_vec(),
             bin0,
-            orig_len,
+            window_len,
             frequency_range: (f_min, f_max),
+            sample_rate,
         }
     }
 
@@ -83,22 +97,29 @@ impl fmt::Debug for SpectrumStore {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct Spectrum {
     spectrum: Vec<Complex<f32>>,
     bin0: usize,
-    orig_len: usize,
+    window_len: usize,
     frequency_range: (f32, f32),
+    sample_rate: usize,
 }
 
 impl Spectrum {
+    // Of the audio the spectrum is computed from.
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
     pub fn bin_for(&self, f: f32, sample_rate: usize) -> usize {
         let (f_min, f_max) = self.frequency_range;
         assert!((f_min..=f_max).contains(&f));
-        bin_for(self.orig_len, f, sample_rate) - self.bin0
+        bin_for(self.window_len, f, sample_rate) - self.bin0
     }
 
     pub fn freq(&self, bin: usize, sample_rate: usize) -> f32 {
-        freq(self.orig_len, self.bin0 + bin, sample_rate)
+        freq(self.window_len, self.bin0 + bin, sample_rate)
     }
 }
 
@@ -110,14 +131,15 @@ impl Deref for Spectrum {
     }
 }
 
-fn bin_for(full_spectrum_size: usize, f: f32, sample_rate: usize) -> usize {
-    let n = full_spectrum_size as f32;
+// The bins are spaced by the sample rate divided by the window length.
+fn bin_for(window_len: usize, f: f32, sample_rate: usize) -> usize {
+    let n = window_len as f32;
     let sr = sample_rate as f32;
     (f * n / sr).round() as usize
 }
 
-fn freq(full_spectrum_size: usize, bin: usize, sample_rate: usize) -> f32 {
-    (bin as f32) * (sample_rate as f32) / (full_spectrum_size as f32)
+fn freq(window_len: usize, bin: usize, sample_rate: usize) -> f32 {
+    (bin as f32) * (sample_rate as f32) / (window_len as f32)
 }
 
 pub trait ComputeSpectrum {
@@ -156,6 +178,15 @@ impl Stft {
     }
 }
 
+impl fmt::Debug for Stft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stft")
+            .field("window_len", &self.input.len())
+            .field("window", &self.window)
+            .finish()
+    }
+}
+
 impl ComputeSpectrum for Stft {
     fn name(&self) -> &'static str {
         "stft"
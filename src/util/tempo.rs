@@ -0,0 +1,190 @@
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Tempo {
+    pub bpm: f32,
+    // From 0 on a beat to 1 just before the next one.
+    pub phase: f32,
+    // From 0 to 1, how periodic the onset strength is.
+    pub confidence: f32,
+}
+
+// Estimates the tempo from the autocorrelation of the onset strength envelope
+// and the phase from aligning a comb of the estimated period with it.
+#[derive(Debug)]
+pub struct TempoEstimator {
+    envelope: VecDeque<f32>,
+    capacity: usize,
+    // Envelope values per second.
+    rate: f32,
+    // Lags corresponding to the maximum and minimum BPM.
+    lags: (usize, usize),
+    centered: Vec<f32>,
+}
+
+impl TempoEstimator {
+    pub fn new(rate: f32, history: f32, bpm_range: (f32, f32)) -> Self {
+        let (bpm_min, bpm_max) = bpm_range;
+        let lag = |bpm: f32| (60.0 * rate / bpm).round().max(1.0) as usize;
+        let lags = (lag(bpm_max), lag(bpm_min));
+
+        // At least two periods of the slowest tempo are needed.
+        let capacity = ((history * rate).ceil() as usize).max(2 * lags.1 + 1);
+
+        Self {
+            envelope: VecDeque::with_capacity(capacity),
+            capacity,
+            rate,
+            lags,
+            centered: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, strength: f32) {
+        if self.envelope.len() == self.capacity {
+            self.envelope.pop_front();
+        }
+        self.envelope.push_back(strength);
+    }
+
+    pub fn estimate(&mut self) -> Option<Tempo> {
+        let (lag_min, lag_max) = self.lags;
+        let n = self.envelope.len();
+
+        if n < 2 * lag_max + 1 {
+            return None;
+        }
+
+        let mean = self.envelope.iter().sum::<f32>() / n as f32;
+        self.centered.clear();
+        self.centered.extend(self.envelope.iter().map(|x| x - mean));
+
+        // Biased, so that multiples of the period do not score the same as
+        // the period itself.
+        let x = &self.centered;
+        let autocorrelation = |lag: usize| {
+            x.iter()
+                .zip(x[lag..].iter())
+                .map(|(a, b)| a * b)
+                .sum::<f32>()
+        };
+
+        let energy = autocorrelation(0);
+        if energy <= f32::EPSILON {
+            return None;
+        }
+
+        let scores = (lag_min - 1..=lag_max + 1)
+            .map(autocorrelation)
+            .collect::<Vec<_>>();
+
+        let (best, peak) = scores[1..scores.len() - 1]
+            .iter()
+            .copied()
+            .enumerate()
+            .fold((0, f32::MIN), |(best, peak), (i, score)| {
+                if score > peak {
+                    (i + 1, score)
+                } else {
+                    (best, peak)
+                }
+            });
+
+        // Parabolic interpolation around the peak for a fractional period.
+        let (left, right) = (scores[best - 1], scores[best + 1]);
+        let denominator = left - 2.0 * peak + right;
+        let offset = if denominator.abs() > f32::EPSILON {
+            (0.5 * (left - right) / denominator).clamp(-0.5, 0.5)
+        } else {
+            0.0
+        };
+        let period = (lag_min + best - 1) as f32 + offset;
+
+        Some(Tempo {
+            bpm: 60.0 * self.rate / period,
+            phase: self.phase(period),
+            confidence: (peak / energy).clamp(0.0, 1.0),
+        })
+    }
+
+    // The offset from the end of the envelope at which a comb with the given
+    // period collects the most onset strength is the time since the last
+    // beat.
+    fn phase(&self, period: f32) -> f32 {
+        let n = self.envelope.len();
+        let beats = (n as f32 / period) as usize;
+
+        let (since_beat, _) = (0..period.round() as usize)
+            .map(|offset| {
+                let strength = (0..beats)
+                    .map(|k| (offset as f32 + k as f32 * period).round() as usize)
+                    .filter(|back| *back < n)
+                    .map(|back| self.envelope[n - 1 - back])
+                    .sum::<f32>();
+                (offset, strength)
+            })
+            .fold((0, f32::MIN), |(best, peak), (offset, strength)| {
+                if strength > peak {
+                    (offset, strength)
+                } else {
+                    (best, peak)
+                }
+            });
+
+        (since_beat as f32 / period).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: f32 = 100.0;
+
+    // Onset strength with a pulse every `period` values, the last one `since`
+    // values before the end.
+    fn estimate(period: usize, since: usize) -> Tempo {
+        let mut estimator = TempoEstimator::new(RATE, 8.0, (60.0, 180.0));
+        let n = 800;
+
+        for i in 0..n {
+            let strength = if (n - 1 - i) % period == since {
+                1.0
+            } else {
+                0.0
+            };
+            estimator.push(strength);
+        }
+
+        estimator.estimate().unwrap()
+    }
+
+    #[test]
+    fn bpm() {
+        // 0.5 s is 120 BPM.
+        let tempo = estimate(50, 0);
+        assert!((tempo.bpm - 120.0).abs() < 1.0, "{tempo:?}");
+        assert!(tempo.confidence > 0.5);
+
+        let tempo = estimate(40, 0);
+        assert!((tempo.bpm - 150.0).abs() < 1.0, "{tempo:?}");
+    }
+
+    #[test]
+    fn phase() {
+        assert_eq!(estimate(50, 0).phase, 0.0);
+        assert!((estimate(50, 25).phase - 0.5).abs() < 0.05);
+        assert!((estimate(50, 40).phase - 0.8).abs() < 0.05);
+    }
+
+    #[test]
+    fn no_estimate_without_onsets() {
+        let mut estimator = TempoEstimator::new(RATE, 8.0, (60.0, 180.0));
+
+        for _ in 0..800 {
+            estimator.push(0.0);
+        }
+
+        assert_eq!(estimator.estimate(), None);
+    }
+}
@@ -1,3 +1,5 @@
+use rayon::prelude::*;
+
 pub struct Pixel<'a> {
     // BGRx pixel encoding
     buf: &'a mut [u8],
@@ -165,6 +167,48 @@ impl VideoFrame {
             }
         }
     }
+
+    // Same as `apply`, but the rows are processed in parallel.
+    pub fn par_apply<F>(&mut self, apply: F)
+    where
+        F: Fn((usize, usize), &mut Pixel) + Sync,
+    {
+        let width = self.width;
+
+        self.buf
+            .par_chunks_exact_mut(self.stride)
+            .enumerate()
+            .for_each(|(y, line)| {
+                for (x, pixel) in line[..(4 * width)].chunks_exact_mut(4).enumerate() {
+                    apply((x, y), &mut Pixel::new(pixel))
+                }
+            });
+    }
+
+    // Same as `apply_zip`, but the rows are processed in parallel.
+    pub fn par_apply_zip<F>(&mut self, other: &mut Self, apply: F)
+    where
+        F: Fn((usize, usize), &mut Pixel, &mut Pixel) + Sync,
+    {
+        assert_eq!(self.width, other.width);
+        assert_eq!(self.height, other.height);
+
+        let width = self.width;
+
+        self.buf
+            .par_chunks_exact_mut(self.stride)
+            .zip(other.buf.par_chunks_exact_mut(other.stride))
+            .enumerate()
+            .for_each(|(y, (line1, line2))| {
+                for (x, (pixel1, pixel2)) in line1[..(4 * width)]
+                    .chunks_exact_mut(4)
+                    .zip(line2[..(4 * width)].chunks_exact_mut(4))
+                    .enumerate()
+                {
+                    apply((x, y), &mut Pixel::new(pixel1), &mut Pixel::new(pixel2))
+                }
+            });
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -172,6 +216,7 @@ pub struct VideoConfig {
     width: usize,
     height: usize,
     fps: usize,
+    offline: bool,
 }
 
 impl VideoConfig {
@@ -187,6 +232,12 @@ impl VideoConfig {
         self.fps
     }
 
+    // In offline mode, frames are rendered as fast as possible instead of in
+    // wall-clock time and sources advance exactly by one frame per `FrameId`.
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
     pub fn builder() -> VideoConfigBuilder {
         VideoConfigBuilder {
             config: VideoConfig::default(),
@@ -200,6 +251,7 @@ impl Default for VideoConfig {
             width: 1280,
             height: 720,
             fps: 24,
+            offline: false,
         }
     }
 }
@@ -230,6 +282,11 @@ impl VideoConfigBuilder {
         self
     }
 
+    pub fn offline(&mut self, value: bool) -> &mut Self {
+        self.config.offline = value;
+        self
+    }
+
     pub fn build(&self) -> VideoConfig {
         self.config
     }